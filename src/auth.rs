@@ -0,0 +1,445 @@
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::github::{GithubClient, User};
+use crate::settings::Settings;
+use crate::task::{self, SharedTask, Task};
+
+const SERVICE: &str = "group-json-manager";
+const ACCOUNT: &str = "github";
+/// Encrypted token, used when the platform keyring isn't available.
+pub const TOKEN_FILE: &str = "group-manager.token";
+/// Overrides any stored token, mainly for CI.
+pub const TOKEN_ENV: &str = "GITHUB_TOKEN";
+/// Passphrase for `TOKEN_FILE` when running from the command line.
+pub const PASSPHRASE_ENV: &str = "GROUP_MANAGER_PASSPHRASE";
+/// Scopes requested by the device flow; enough to fork and open PRs.
+const SCOPES: &str = "public_repo";
+
+/// Where the current token came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Environment,
+    Keyring,
+    File,
+}
+
+impl Source {
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Environment => TOKEN_ENV,
+            Source::Keyring => "system keyring",
+            Source::File => TOKEN_FILE,
+        }
+    }
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, ACCOUNT).map_err(|e| format!("Keyring unavailable: {}", e))
+}
+
+/// The token to send with GitHub requests, if any. The token file needs
+/// `passphrase`; without one an existing file is reported as locked.
+pub fn load_token(passphrase: Option<&str>) -> Result<Option<(String, Source)>, String> {
+    if let Ok(token) = std::env::var(TOKEN_ENV) {
+        if !token.trim().is_empty() {
+            return Ok(Some((token.trim().to_string(), Source::Environment)));
+        }
+    }
+    if let Ok(token) = keyring_entry().and_then(|entry| {
+        entry
+            .get_password()
+            .map_err(|e| format!("Error reading keyring: {}", e))
+    }) {
+        return Ok(Some((token, Source::Keyring)));
+    }
+    let Ok(text) = std::fs::read_to_string(TOKEN_FILE) else {
+        return Ok(None);
+    };
+    let Some(passphrase) = passphrase.filter(|p| !p.is_empty()) else {
+        return Err(format!("{} is locked, enter its passphrase", TOKEN_FILE));
+    };
+    let file: TokenFile = serde_json::from_str(&text)
+        .map_err(|e| format!("Error deserializing {}: {}", TOKEN_FILE, e))?;
+    file.decrypt(passphrase)
+        .map(|token| Some((token, Source::File)))
+}
+
+/// Stores `token` in the keyring, falling back to an encrypted file when
+/// the keyring can't be used and a passphrase was given.
+pub fn save_token(token: &str, passphrase: Option<&str>) -> Result<Source, String> {
+    let stored = keyring_entry().and_then(|entry| {
+        entry
+            .set_password(token)
+            .map_err(|e| format!("Error writing keyring: {}", e))
+    });
+    match stored {
+        Ok(()) => {
+            let _ = std::fs::remove_file(TOKEN_FILE);
+            Ok(Source::Keyring)
+        }
+        Err(e) => {
+            let Some(passphrase) = passphrase.filter(|p| !p.is_empty()) else {
+                return Err(format!(
+                    "{}; enter a passphrase to store the token in {} instead",
+                    e, TOKEN_FILE
+                ));
+            };
+            let json = serde_json::to_vec_pretty(&TokenFile::encrypt(token, passphrase)?).unwrap();
+            std::fs::write(TOKEN_FILE, json)
+                .map_err(|e| format!("Error writing {}: {}", TOKEN_FILE, e))?;
+            Ok(Source::File)
+        }
+    }
+}
+
+/// Removes the token from the keyring and the token file.
+pub fn forget_token() -> Result<(), String> {
+    if let Ok(entry) = keyring_entry() {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(format!("Error deleting from keyring: {}", e)),
+        }
+    }
+    match std::fs::remove_file(TOKEN_FILE) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Error deleting {}: {}", TOKEN_FILE, e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// AES-256-GCM encrypted token with an Argon2 derived key.
+#[derive(Debug, Deserialize, Serialize)]
+struct TokenFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Error deriving key: {}", e))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|e| format!("Error deriving key: {}", e))
+}
+
+impl TokenFile {
+    fn encrypt(token: &str, passphrase: &str) -> Result<Self, String> {
+        let salt: [u8; 16] = rand::random();
+        let nonce: [u8; 12] = rand::random();
+        let ciphertext = derive_key(passphrase, &salt)?
+            .encrypt(Nonce::from_slice(&nonce), token.as_bytes())
+            .map_err(|_| "Error encrypting token".to_string())?;
+        Ok(Self {
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    fn decrypt(&self, passphrase: &str) -> Result<String, String> {
+        let decode = |field: &str| {
+            STANDARD
+                .decode(field)
+                .map_err(|e| format!("Error decoding {}: {}", TOKEN_FILE, e))
+        };
+        let salt = decode(&self.salt)?;
+        let nonce = decode(&self.nonce)?;
+        if nonce.len() != 12 {
+            return Err(format!("Error decoding {}: bad nonce", TOKEN_FILE));
+        }
+        let plaintext = derive_key(passphrase, &salt)?
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                decode(&self.ciphertext)?.as_slice(),
+            )
+            .map_err(|_| "Wrong passphrase".to_string())?;
+        String::from_utf8(plaintext).map_err(|e| format!("Error decoding token: {}", e))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+    interval: Option<u64>,
+}
+
+fn login_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(concat!("group-json-manager/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap()
+}
+
+/// Starts the OAuth device flow. `login_url` is github.com, or a mock.
+pub async fn request_device_code(login_url: &str, client_id: &str) -> Result<DeviceCode, String> {
+    login_client()
+        .post(format!(
+            "{}/login/device/code",
+            login_url.trim_end_matches('/')
+        ))
+        .header("Accept", "application/json")
+        .form(&[("client_id", client_id), ("scope", SCOPES)])
+        .send()
+        .await
+        .map_err(|e| format!("Error fetching: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Error fetching: {}", e))?
+        .json::<DeviceCode>()
+        .await
+        .map_err(|e| format!("Error deserializing: {}", e))
+}
+
+/// Polls until the user has entered `code.user_code`, returning the token.
+pub async fn poll_device_token(
+    login_url: &str,
+    client_id: &str,
+    code: &DeviceCode,
+) -> Result<String, String> {
+    let client = login_client();
+    let url = format!(
+        "{}/login/oauth/access_token",
+        login_url.trim_end_matches('/')
+    );
+    let mut interval = code.interval.max(1);
+    let deadline = std::time::Instant::now() + Duration::from_secs(code.expires_in);
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if std::time::Instant::now() > deadline {
+            return Err("The device code expired, start again".to_string());
+        }
+        let response: TokenResponse = client
+            .post(&url)
+            .header("Accept", "application/json")
+            .form(&[
+                ("client_id", client_id),
+                ("device_code", code.device_code.as_str()),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("Error fetching: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Error deserializing: {}", e))?;
+        if let Some(token) = response.access_token {
+            return Ok(token);
+        }
+        match response.error.as_deref() {
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval = response.interval.unwrap_or(interval + 5),
+            Some(error) => {
+                return Err(response
+                    .error_description
+                    .unwrap_or_else(|| error.to_string()))
+            }
+            None => return Err("GitHub returned neither a token nor an error".to_string()),
+        }
+    }
+}
+
+/// The "GitHub account" section: shows who is signed in and lets the user
+/// paste a personal access token or go through the device flow.
+#[derive(Debug, Clone, Default)]
+pub struct AuthPanel {
+    loaded: bool,
+    token: Option<(String, Source)>,
+    pat: String,
+    passphrase: String,
+    message: Option<Result<String, String>>,
+    user: SharedTask<Result<Option<User>, String>>,
+    device_code: SharedTask<Result<DeviceCode, String>>,
+    device_token: SharedTask<Result<String, String>>,
+}
+
+impl AuthPanel {
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_ref().map(|(token, _)| token.as_str())
+    }
+
+    fn load(&mut self, ctx: &egui::Context, settings: &Settings) {
+        self.loaded = true;
+        match load_token(Some(&self.passphrase)) {
+            Ok(token) => {
+                self.token = token;
+                self.message = None;
+                self.verify(ctx, settings);
+            }
+            Err(e) => self.message = Some(Err(e)),
+        }
+    }
+
+    fn store(&mut self, ctx: &egui::Context, settings: &Settings, token: &str) {
+        match save_token(token, Some(&self.passphrase)) {
+            Ok(source) => {
+                self.pat.clear();
+                self.message = Some(Ok(format!("Token saved to {}", source.label())));
+                self.token = Some((token.to_string(), source));
+                self.verify(ctx, settings);
+            }
+            Err(e) => self.message = Some(Err(e)),
+        }
+    }
+
+    fn verify(&self, ctx: &egui::Context, settings: &Settings) {
+        if self.token.is_none() {
+            *self.user.lock().unwrap() = Task::Idle;
+            return;
+        }
+        let client = GithubClient::new(&settings.github_api_url).with_token(self.token());
+        task::spawn(ctx, &self.user, async move { client.user().await });
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, settings: &Settings) {
+        let ctx = ui.ctx().clone();
+        if !self.loaded {
+            self.load(&ctx, settings);
+        }
+
+        let finished = match &*self.device_token.lock().unwrap() {
+            Task::Done(result) => Some(result.clone()),
+            _ => None,
+        };
+        if let Some(result) = finished {
+            *self.device_token.lock().unwrap() = Task::Idle;
+            *self.device_code.lock().unwrap() = Task::Idle;
+            match result {
+                Ok(token) => self.store(&ctx, settings, &token),
+                Err(e) => self.message = Some(Err(e)),
+            }
+        }
+
+        egui::CollapsingHeader::new("GitHub account")
+            .id_source("github_account")
+            .show(ui, |ui| {
+                match (&self.token, &*self.user.lock().unwrap()) {
+                    (None, _) => {
+                        ui.label("Not signed in, GitHub allows 60 requests an hour");
+                    }
+                    (Some(_), Task::Idle | Task::Running) => {
+                        ui.spinner();
+                    }
+                    (Some((_, source)), Task::Done(Ok(Some(user)))) => {
+                        ui.label(format!("Signed in as {} ({})", user.login, source.label()));
+                    }
+                    (Some(_), Task::Done(Ok(None))) => {
+                        ui.colored_label(Color32::LIGHT_RED, "The API has no /user endpoint");
+                    }
+                    (Some(_), Task::Done(Err(e))) => {
+                        ui.colored_label(Color32::LIGHT_RED, format!("Token rejected: {}", e));
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Passphrase for the token file");
+                    ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true));
+                    if self.token.is_none()
+                        && std::path::Path::new(TOKEN_FILE).exists()
+                        && ui.button("Unlock").clicked()
+                    {
+                        self.load(&ctx, settings);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Personal access token");
+                    ui.add(egui::TextEdit::singleline(&mut self.pat).password(true));
+                    if ui
+                        .add_enabled(!self.pat.trim().is_empty(), egui::Button::new("Save"))
+                        .clicked()
+                    {
+                        let pat = self.pat.trim().to_string();
+                        self.store(&ctx, settings, &pat);
+                    }
+                });
+
+                let code = self.device_code.lock().unwrap().clone();
+                match code {
+                    Task::Idle => {
+                        let enabled = !settings.github_client_id.is_empty();
+                        let button = ui
+                            .add_enabled(enabled, egui::Button::new("Sign in with GitHub"))
+                            .on_disabled_hover_text("Set an OAuth client ID in the settings");
+                        if button.clicked() {
+                            let login_url = settings.github_login_url.clone();
+                            let client_id = settings.github_client_id.clone();
+                            task::spawn(&ctx, &self.device_code, async move {
+                                request_device_code(&login_url, &client_id).await
+                            });
+                        }
+                    }
+                    Task::Running => {
+                        ui.spinner();
+                    }
+                    Task::Done(Err(e)) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                        if ui.button("Dismiss").clicked() {
+                            *self.device_code.lock().unwrap() = Task::Idle;
+                        }
+                    }
+                    Task::Done(Ok(code)) => {
+                        ui.horizontal(|ui| {
+                            ui.label("Enter");
+                            ui.label(egui::RichText::new(&code.user_code).strong().monospace());
+                            ui.label("at");
+                            ui.hyperlink(&code.verification_uri);
+                            if ui.small_button("Copy code").clicked() {
+                                ui.output_mut(|o| o.copied_text = code.user_code.clone());
+                            }
+                        });
+                        if !self.device_token.lock().unwrap().is_running() {
+                            let login_url = settings.github_login_url.clone();
+                            let client_id = settings.github_client_id.clone();
+                            task::spawn(&ctx, &self.device_token, async move {
+                                poll_device_token(&login_url, &client_id, &code).await
+                            });
+                        }
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Waiting for GitHub");
+                        });
+                    }
+                }
+
+                if let Some((_, source)) = self.token {
+                    if source != Source::Environment && ui.button("Sign out").clicked() {
+                        match forget_token() {
+                            Ok(()) => {
+                                self.token = None;
+                                self.message = Some(Ok("Signed out".to_string()));
+                                self.verify(&ctx, settings);
+                            }
+                            Err(e) => self.message = Some(Err(e)),
+                        }
+                    }
+                }
+                match &self.message {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                    }
+                    None => {}
+                }
+            });
+    }
+}
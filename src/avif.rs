@@ -0,0 +1,368 @@
+use std::ffi::c_int;
+use std::io::Cursor;
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+
+use image::{DynamicImage, RgbaImage};
+use rav1d::include::dav1d::data::Dav1dData;
+use rav1d::include::dav1d::dav1d::{Dav1dContext, Dav1dSettings};
+use rav1d::include::dav1d::headers::{
+    Dav1dMatrixCoefficients, Dav1dPixelLayout, DAV1D_MC_BT2020_CL, DAV1D_MC_BT2020_NCL,
+    DAV1D_MC_BT709, DAV1D_MC_IDENTITY, DAV1D_MC_UNKNOWN, DAV1D_PIXEL_LAYOUT_I400,
+    DAV1D_PIXEL_LAYOUT_I420, DAV1D_PIXEL_LAYOUT_I422,
+};
+use rav1d::include::dav1d::picture::Dav1dPicture;
+use rav1d::src::lib::{
+    dav1d_close, dav1d_data_create, dav1d_data_unref, dav1d_default_settings, dav1d_get_picture,
+    dav1d_open, dav1d_picture_unref, dav1d_send_data,
+};
+
+// `image` only decodes AVIF through dav1d linked as a system library, which
+// the Windows build does not have, so AVIF previews are decoded here with
+// rav1d, the Rust port of dav1d, and avif-parse for the container. rav1d
+// only exposes dav1d's C API, hence the unsafe calls; the module is behind
+// the `avif` feature so builds without it carry none of them.
+
+/// Width and height of an AVIF image, read from its headers.
+pub fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let avif = avif_parse::read_avif(&mut Cursor::new(bytes)).ok()?;
+    let metadata = avif.primary_item_metadata().ok()?;
+    Some((
+        metadata.max_frame_width.get(),
+        metadata.max_frame_height.get(),
+    ))
+}
+
+/// Decodes an AVIF image, with its alpha channel if it has one.
+pub fn decode(bytes: &[u8]) -> Result<DynamicImage, String> {
+    let avif = avif_parse::read_avif(&mut Cursor::new(bytes))
+        .map_err(|e| format!("Error reading AVIF container: {}", e))?;
+    let colour = Frame::decode(&avif.primary_item)?;
+    let alpha = match &avif.alpha_item {
+        Some(item) => Some(Frame::decode(item)?),
+        None => None,
+    };
+
+    let mut image = RgbaImage::new(colour.width, colour.height);
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let [r, g, b] = colour.rgb(x, y);
+        let a = match &alpha {
+            Some(alpha) if x < alpha.width && y < alpha.height => alpha.luma(x, y),
+            _ => 255,
+        };
+        pixel.0 = if avif.premultiplied_alpha && a > 0 && a < 255 {
+            let unmultiply = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+            [unmultiply(r), unmultiply(g), unmultiply(b), a]
+        } else {
+            [r, g, b, a]
+        };
+    }
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+/// One decoded AV1 frame, copied out of the decoder's buffers.
+struct Frame {
+    width: u32,
+    height: u32,
+    bits: u32,
+    layout: Dav1dPixelLayout,
+    matrix: Dav1dMatrixCoefficients,
+    full_range: bool,
+    /// Y, U and V samples, one row after another.
+    planes: [Vec<u16>; 3],
+    chroma_width: u32,
+}
+
+/// Closes the decoder however decoding ends.
+struct Decoder(Option<Dav1dContext>);
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        // SAFETY: the context is either `None`, if `dav1d_open` never ran or
+        // failed, or one it opened. `dav1d_close` accepts both and sets it to
+        // `None`, and this is the only place it is closed.
+        unsafe { dav1d_close(NonNull::new(&mut self.0)) };
+    }
+}
+
+fn check(result: c_int, what: &str) -> Result<(), String> {
+    if result < 0 {
+        Err(format!("Error decoding AVIF: {} failed ({})", what, result))
+    } else {
+        Ok(())
+    }
+}
+
+impl Frame {
+    fn decode(obus: &[u8]) -> Result<Self, String> {
+        let mut settings = MaybeUninit::<Dav1dSettings>::uninit();
+        // SAFETY: the pointer is to a live local, and `dav1d_default_settings`
+        // writes every field before it is read.
+        let mut settings = unsafe {
+            dav1d_default_settings(NonNull::new(settings.as_mut_ptr()).unwrap());
+            settings.assume_init()
+        };
+        settings.n_threads = 1;
+        settings.max_frame_delay = 1;
+
+        let mut decoder = Decoder(None);
+        // SAFETY: both pointers are to live locals. On success `decoder` owns
+        // the context and closes it when dropped.
+        let result =
+            unsafe { dav1d_open(NonNull::new(&mut decoder.0), NonNull::new(&mut settings)) };
+        check(result.0, "opening the decoder")?;
+
+        let mut data = Dav1dData::default();
+        // SAFETY: `data` is a live local; the buffer it gets is `obus.len()`
+        // bytes long, so the copy stays inside it.
+        unsafe {
+            let buffer = dav1d_data_create(NonNull::new(&mut data), obus.len());
+            if buffer.is_null() {
+                return Err("Error decoding AVIF: out of memory".to_string());
+            }
+            std::ptr::copy_nonoverlapping(obus.as_ptr(), buffer, obus.len());
+        }
+
+        // dav1d asks for more data or to be asked again with -EAGAIN.
+        let again = -libc::EAGAIN;
+        let mut picture = Dav1dPicture::default();
+        // Once all the data is in, asking again drains the decoder.
+        let mut drains = 0;
+        loop {
+            if data.sz > 0 {
+                // SAFETY: the context is open and `data` came from
+                // `dav1d_data_create`.
+                let result = unsafe { dav1d_send_data(decoder.0, NonNull::new(&mut data)) };
+                if result.0 < 0 && result.0 != again {
+                    // SAFETY: `data` came from `dav1d_data_create` and still
+                    // holds its buffer; it is not used again.
+                    unsafe { dav1d_data_unref(NonNull::new(&mut data)) };
+                    check(result.0, "reading the image data")?;
+                }
+            }
+            // SAFETY: the context is open and `picture` is a live local.
+            let result = unsafe { dav1d_get_picture(decoder.0, NonNull::new(&mut picture)) };
+            if result.0 == again {
+                if data.sz == 0 {
+                    drains += 1;
+                    if drains > 2 {
+                        return Err("Error decoding AVIF: no picture in the image data".to_string());
+                    }
+                }
+                continue;
+            }
+            if data.sz > 0 {
+                // SAFETY: `data` came from `dav1d_data_create` and still holds
+                // the rest of its buffer; it is not used again.
+                unsafe { dav1d_data_unref(NonNull::new(&mut data)) };
+            }
+            check(result.0, "decoding the picture")?;
+            break;
+        }
+
+        // SAFETY: `picture` was filled in by `dav1d_get_picture`, which
+        // succeeded, and has not been released.
+        let frame = unsafe { Self::copy(&picture) };
+        // SAFETY: as above; the copy holds no pointers into the picture, and
+        // `picture` is not used after being released.
+        unsafe { dav1d_picture_unref(NonNull::new(&mut picture)) };
+        frame
+    }
+
+    /// Copies the planes out of a decoded picture.
+    ///
+    /// # Safety
+    ///
+    /// `picture` must be a picture returned by `dav1d_get_picture` that has
+    /// not been released yet.
+    unsafe fn copy(picture: &Dav1dPicture) -> Result<Self, String> {
+        let width = picture.p.w.max(0) as usize;
+        let height = picture.p.h.max(0) as usize;
+        let bits = picture.p.bpc as u32;
+        let layout = picture.p.layout;
+        let (matrix, full_range) = match picture.seq_hdr {
+            Some(header) => {
+                // SAFETY: the picture holds a reference to its sequence
+                // header, which the caller guarantees is still live.
+                let header = unsafe { header.as_ref() };
+                (header.mtrx, header.color_range != 0)
+            }
+            None => (DAV1D_MC_UNKNOWN, false),
+        };
+        let (chroma_width, chroma_height) = match layout {
+            DAV1D_PIXEL_LAYOUT_I400 => (0, 0),
+            DAV1D_PIXEL_LAYOUT_I420 => (width.div_ceil(2), height.div_ceil(2)),
+            DAV1D_PIXEL_LAYOUT_I422 => (width.div_ceil(2), height),
+            _ => (width, height),
+        };
+
+        let plane = |index: usize, w: usize, h: usize| -> Vec<u16> {
+            let Some(data) = picture.data[index] else {
+                return Vec::new();
+            };
+            let stride = picture.stride[index.min(1)];
+            let mut samples = Vec::with_capacity(w * h);
+            for row in 0..h {
+                // SAFETY: dav1d allocates each plane as `h` rows `stride`
+                // bytes apart (the U and V planes share `stride[1]`), each
+                // holding at least `w` samples: bytes at 8 bits, otherwise
+                // `u16`s, which the 64-byte aligned rows keep aligned. The
+                // caller guarantees the picture, and so the plane, is live.
+                unsafe {
+                    let start = (data.as_ptr() as *const u8).offset(row as isize * stride);
+                    if bits > 8 {
+                        let row = std::slice::from_raw_parts(start as *const u16, w);
+                        samples.extend_from_slice(row);
+                    } else {
+                        let row = std::slice::from_raw_parts(start, w);
+                        samples.extend(row.iter().map(|&sample| sample as u16));
+                    }
+                }
+            }
+            samples
+        };
+        let planes = [
+            plane(0, width, height),
+            plane(1, chroma_width, chroma_height),
+            plane(2, chroma_width, chroma_height),
+        ];
+        if planes[0].len() != width * height {
+            return Err("Error decoding AVIF: the picture has no image data".to_string());
+        }
+        Ok(Self {
+            width: width as u32,
+            height: height as u32,
+            bits,
+            layout,
+            matrix,
+            full_range,
+            planes,
+            chroma_width: chroma_width as u32,
+        })
+    }
+
+    fn sample(&self, plane: usize, x: u32, y: u32) -> f32 {
+        let (x, y, width) = if plane == 0 {
+            (x, y, self.width)
+        } else {
+            match self.layout {
+                DAV1D_PIXEL_LAYOUT_I420 => (x / 2, y / 2, self.chroma_width),
+                DAV1D_PIXEL_LAYOUT_I422 => (x / 2, y, self.chroma_width),
+                _ => (x, y, self.chroma_width),
+            }
+        };
+        self.planes[plane][(y * width + x) as usize] as f32
+    }
+
+    /// Luma (or alpha, for an alpha frame) scaled to 0..=1.
+    fn unit_luma(&self, x: u32, y: u32) -> f32 {
+        let scale = (1u32 << (self.bits - 8)) as f32;
+        let sample = self.sample(0, x, y);
+        if self.full_range {
+            sample / (255.0 * scale)
+        } else {
+            (sample - 16.0 * scale) / (219.0 * scale)
+        }
+    }
+
+    /// Chroma scaled to -0.5..=0.5.
+    fn unit_chroma(&self, plane: usize, x: u32, y: u32) -> f32 {
+        let scale = (1u32 << (self.bits - 8)) as f32;
+        let sample = self.sample(plane, x, y);
+        if self.full_range {
+            (sample - 128.0 * scale) / (255.0 * scale)
+        } else {
+            (sample - 128.0 * scale) / (224.0 * scale)
+        }
+    }
+
+    fn luma(&self, x: u32, y: u32) -> u8 {
+        to_u8(self.unit_luma(x, y))
+    }
+
+    fn rgb(&self, x: u32, y: u32) -> [u8; 3] {
+        let luma = self.unit_luma(x, y);
+        if self.layout == DAV1D_PIXEL_LAYOUT_I400 {
+            let grey = to_u8(luma);
+            return [grey, grey, grey];
+        }
+        let cb = self.unit_chroma(1, x, y);
+        let cr = self.unit_chroma(2, x, y);
+        if self.matrix == DAV1D_MC_IDENTITY {
+            // GBR stored in the Y, U and V planes.
+            return [to_u8(cr + 0.5), to_u8(luma), to_u8(cb + 0.5)];
+        }
+        // Unspecified matrices are taken as BT.601, like other decoders do.
+        let (kr, kb) = match self.matrix {
+            DAV1D_MC_BT709 => (0.2126, 0.0722),
+            DAV1D_MC_BT2020_NCL | DAV1D_MC_BT2020_CL => (0.2627, 0.0593),
+            _ => (0.299, 0.114),
+        };
+        let kg = 1.0 - kr - kb;
+        let r = luma + 2.0 * (1.0 - kr) * cr;
+        let b = luma + 2.0 * (1.0 - kb) * cb;
+        let g = (luma - kr * r - kb * b) / kg;
+        [to_u8(r), to_u8(g), to_u8(b)]
+    }
+}
+
+fn to_u8(unit: f32) -> u8 {
+    (unit * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::avif::AvifEncoder;
+    use image::{ImageEncoder, Rgba};
+
+    fn encode(image: &RgbaImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        AvifEncoder::new_with_speed_quality(&mut bytes, 10, 100)
+            .write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                image::ExtendedColorType::Rgba8,
+            )
+            .unwrap();
+        bytes
+    }
+
+    fn close(a: Rgba<u8>, b: [u8; 4]) -> bool {
+        a.0.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 12)
+    }
+
+    #[test]
+    fn decodes_colours_and_size() {
+        let image = RgbaImage::from_fn(64, 48, |x, y| match (x < 32, y < 24) {
+            (true, _) => Rgba([220, 30, 30, 255]),
+            (false, true) => Rgba([30, 200, 40, 255]),
+            (false, false) => Rgba([20, 40, 230, 255]),
+        });
+        let bytes = encode(&image);
+        assert_eq!(dimensions(&bytes), Some((64, 48)));
+
+        let decoded = decode(&bytes).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (64, 48));
+        assert!(close(*decoded.get_pixel(8, 8), [220, 30, 30, 255]));
+        assert!(close(*decoded.get_pixel(56, 8), [30, 200, 40, 255]));
+        assert!(close(*decoded.get_pixel(56, 40), [20, 40, 230, 255]));
+    }
+
+    #[test]
+    fn decodes_alpha() {
+        let image = RgbaImage::from_fn(40, 40, |x, _| {
+            Rgba([200, 100, 50, if x < 20 { 255 } else { 0 }])
+        });
+        let decoded = decode(&encode(&image)).unwrap().to_rgba8();
+        assert!(close(*decoded.get_pixel(4, 4), [200, 100, 50, 255]));
+        assert_eq!(decoded.get_pixel(36, 4).0[3], 0);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(decode(b"not an avif").is_err());
+        assert_eq!(dimensions(b"not an avif"), None);
+    }
+}
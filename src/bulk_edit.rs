@@ -0,0 +1,583 @@
+use std::collections::{BTreeSet, HashMap};
+
+use eframe::egui::{self, Color32, ComboBox, Window};
+
+use crate::types::{GroupData, Project};
+use crate::widgets::Confirm;
+
+/// A project field that can be edited in bulk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Background,
+    PageBackground,
+    Overview,
+    Description,
+    Changelog,
+    PackageOwner,
+    PackageRepo,
+    PackageFile,
+}
+
+impl Field {
+    pub const ALL: [Field; 8] = [
+        Field::Background,
+        Field::PageBackground,
+        Field::Overview,
+        Field::Description,
+        Field::Changelog,
+        Field::PackageOwner,
+        Field::PackageRepo,
+        Field::PackageFile,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Background => "Background",
+            Field::PageBackground => "Page background",
+            Field::Overview => "Overview",
+            Field::Description => "Description",
+            Field::Changelog => "Changelog",
+            Field::PackageOwner => "Package owner",
+            Field::PackageRepo => "Package repo name",
+            Field::PackageFile => "Package file name",
+        }
+    }
+
+    /// The field's value, `None` when it is not set. Projects without a
+    /// package have no package fields to edit.
+    fn get(self, project: &Project) -> Result<Option<String>, &'static str> {
+        let package = || project.package.as_ref().ok_or("no package");
+        Ok(match self {
+            Field::Background => Some(project.background.clone()),
+            Field::PageBackground => project.pageBackground.clone(),
+            Field::Overview => Some(project.overview.clone()),
+            Field::Description => Some(project.description.clone()),
+            Field::Changelog => Some(project.changelog.clone()),
+            Field::PackageOwner => Some(package()?.owner.clone()),
+            Field::PackageRepo => Some(package()?.repoName.clone()),
+            Field::PackageFile => Some(package()?.fileName.clone()),
+        })
+    }
+
+    /// Sets the field; `None` only unsets optional fields and empties the
+    /// others.
+    fn set(self, project: &mut Project, value: Option<String>) {
+        let text = value.clone().unwrap_or_default();
+        match self {
+            Field::Background => project.background = text,
+            Field::PageBackground => project.pageBackground = value,
+            Field::Overview => project.overview = text,
+            Field::Description => project.description = text,
+            Field::Changelog => project.changelog = text,
+            Field::PackageOwner | Field::PackageRepo | Field::PackageFile => {
+                let Some(package) = project.package.as_mut() else {
+                    return;
+                };
+                match self {
+                    Field::PackageOwner => package.owner = text,
+                    Field::PackageRepo => package.repoName = text,
+                    _ => package.fileName = text,
+                }
+            }
+        }
+    }
+}
+
+/// What to do to the field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Set,
+    Clear,
+    Prefix,
+    Suffix,
+    Replace,
+    Trim,
+    Lowercase,
+    Uppercase,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 8] = [
+        Operation::Set,
+        Operation::Clear,
+        Operation::Prefix,
+        Operation::Suffix,
+        Operation::Replace,
+        Operation::Trim,
+        Operation::Lowercase,
+        Operation::Uppercase,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Operation::Set => "Set to",
+            Operation::Clear => "Clear",
+            Operation::Prefix => "Add prefix",
+            Operation::Suffix => "Add suffix",
+            Operation::Replace => "Replace text",
+            Operation::Trim => "Trim whitespace",
+            Operation::Lowercase => "Lower case",
+            Operation::Uppercase => "Upper case",
+        }
+    }
+}
+
+/// A bulk edit of one field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub field: Field,
+    pub operation: Operation,
+    /// The new value, prefix, suffix or replacement.
+    pub value: String,
+    /// The text `Operation::Replace` looks for.
+    pub find: String,
+}
+
+impl Default for Edit {
+    fn default() -> Self {
+        Self {
+            field: Field::Background,
+            operation: Operation::Set,
+            value: String::new(),
+            find: String::new(),
+        }
+    }
+}
+
+impl Edit {
+    /// The value `field` ends up with. Clearing an optional field unsets it;
+    /// other edits of an unset field start from an empty string.
+    fn edited(&self, current: Option<&str>) -> Option<String> {
+        let text = current.unwrap_or_default();
+        let edited = match self.operation {
+            Operation::Set => self.value.clone(),
+            Operation::Clear if self.field == Field::PageBackground => return None,
+            Operation::Clear => String::new(),
+            Operation::Prefix => format!("{}{}", self.value, text),
+            Operation::Suffix => format!("{}{}", text, self.value),
+            Operation::Replace if self.find.is_empty() => text.to_string(),
+            Operation::Replace => text.replace(&self.find, &self.value),
+            Operation::Trim => text.trim().to_string(),
+            Operation::Lowercase => text.to_lowercase(),
+            Operation::Uppercase => text.to_uppercase(),
+        };
+        // Transforms leave an unset field unset when they have nothing to
+        // work on.
+        if current.is_none() && edited.is_empty() {
+            return None;
+        }
+        Some(edited)
+    }
+}
+
+/// What a bulk edit does to one selected project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub group: String,
+    pub index: usize,
+    pub project: String,
+    pub before: Option<String>,
+    /// The new value, or why the project is left alone.
+    pub after: Result<Option<String>, &'static str>,
+}
+
+impl Change {
+    pub fn changes(&self) -> bool {
+        matches!(&self.after, Ok(after) if *after != self.before)
+    }
+}
+
+/// State of the "Bulk edit" window: the selected projects and the edit.
+#[derive(Debug, Clone, Default)]
+pub struct BulkEditWindow {
+    /// Group and project name of each selected project, in order. Names
+    /// rather than indices, so the selection stays on the same projects when
+    /// others are added, removed or reordered while the window is open.
+    selection: BTreeSet<(String, String)>,
+    edit: Edit,
+}
+
+impl BulkEditWindow {
+    /// Starts with `project` of `group` selected, if there is one.
+    pub fn new(group: &str, project: Option<&str>) -> Self {
+        Self {
+            selection: project
+                .map(|name| (group.to_string(), name.to_string()))
+                .into_iter()
+                .collect(),
+            edit: Edit::default(),
+        }
+    }
+
+    /// What the edit does to each selected project.
+    pub fn preview(&self, groups: &HashMap<String, GroupData>) -> Vec<Change> {
+        self.selection
+            .iter()
+            .filter_map(|(group, name)| Some((group, groups.get(group)?, name)))
+            .flat_map(|(group, data, name)| {
+                data.projects
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, project)| project.name == *name)
+                    .map(move |(index, project)| (group, index, project))
+            })
+            .map(|(group, index, project)| {
+                let (before, after) = match self.edit.field.get(project) {
+                    Ok(before) => {
+                        let after = self.edit.edited(before.as_deref());
+                        (before, Ok(after))
+                    }
+                    Err(reason) => (None, Err(reason)),
+                };
+                Change {
+                    group: group.clone(),
+                    index,
+                    project: project.name.clone(),
+                    before,
+                    after,
+                }
+            })
+            .collect()
+    }
+
+    /// Applies the edit to every selected project it changes.
+    pub fn apply(&self, groups: &mut HashMap<String, GroupData>) {
+        for change in self.preview(groups) {
+            let Ok(after) = change.after else {
+                continue;
+            };
+            if let Some(project) = groups
+                .get_mut(&change.group)
+                .and_then(|group| group.projects.get_mut(change.index))
+            {
+                self.edit.field.set(project, after);
+            }
+        }
+    }
+
+    fn selection_ui(&mut self, ui: &mut egui::Ui, groups: &HashMap<String, GroupData>) {
+        let mut names: Vec<&String> = groups.keys().collect();
+        names.sort_by_key(|name| name.to_lowercase());
+        ui.horizontal(|ui| {
+            ui.label(format!("{} projects selected", self.selection.len()));
+            if ui.button("Select all").clicked() {
+                for name in &names {
+                    for project in &groups[*name].projects {
+                        self.selection
+                            .insert(((*name).clone(), project.name.clone()));
+                    }
+                }
+            }
+            if ui.button("Select none").clicked() {
+                self.selection.clear();
+            }
+        });
+        egui::ScrollArea::vertical()
+            .id_source("bulk_edit_projects")
+            .max_height(250.0)
+            .show(ui, |ui| {
+                for name in names {
+                    let projects = &groups[name].projects;
+                    let key = |project: &Project| (name.clone(), project.name.clone());
+                    let selected = projects
+                        .iter()
+                        .filter(|project| self.selection.contains(&key(project)))
+                        .count();
+                    egui::CollapsingHeader::new(format!(
+                        "{} ({}/{})",
+                        name,
+                        selected,
+                        projects.len()
+                    ))
+                    .id_source(("bulk_edit_group", name))
+                    .show(ui, |ui| {
+                        let mut all = selected == projects.len() && selected > 0;
+                        if ui.checkbox(&mut all, "All projects").changed() {
+                            for project in projects {
+                                if all {
+                                    self.selection.insert(key(project));
+                                } else {
+                                    self.selection.remove(&key(project));
+                                }
+                            }
+                        }
+                        for project in projects {
+                            let key = key(project);
+                            let mut checked = self.selection.contains(&key);
+                            if ui.checkbox(&mut checked, &project.name).changed() {
+                                if checked {
+                                    self.selection.insert(key);
+                                } else {
+                                    self.selection.remove(&key);
+                                }
+                            }
+                        }
+                    });
+                }
+            });
+    }
+
+    fn edit_ui(&mut self, ui: &mut egui::Ui) {
+        let edit = &mut self.edit;
+        ui.horizontal(|ui| {
+            ComboBox::from_id_source("bulk_edit_field")
+                .selected_text(edit.field.label())
+                .show_ui(ui, |ui| {
+                    for field in Field::ALL {
+                        ui.selectable_value(&mut edit.field, field, field.label());
+                    }
+                });
+            ComboBox::from_id_source("bulk_edit_operation")
+                .selected_text(edit.operation.label())
+                .show_ui(ui, |ui| {
+                    for operation in Operation::ALL {
+                        ui.selectable_value(&mut edit.operation, operation, operation.label());
+                    }
+                });
+            match edit.operation {
+                Operation::Set | Operation::Prefix | Operation::Suffix => {
+                    ui.text_edit_singleline(&mut edit.value);
+                }
+                Operation::Replace => {
+                    ui.text_edit_singleline(&mut edit.find);
+                    ui.label("with");
+                    ui.text_edit_singleline(&mut edit.value);
+                }
+                _ => {}
+            }
+        });
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context, groups: &HashMap<String, GroupData>) -> Confirm {
+        // Projects removed or renamed since they were selected drop out.
+        self.selection.retain(|(group, name)| {
+            groups
+                .get(group)
+                .is_some_and(|group| group.projects.iter().any(|p| p.name == *name))
+        });
+
+        let mut action = Confirm::None;
+        let mut open = true;
+        Window::new("Bulk edit")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                self.selection_ui(ui, groups);
+                ui.separator();
+                self.edit_ui(ui);
+                ui.separator();
+
+                let changes = self.preview(groups);
+                let changed = changes.iter().filter(|change| change.changes()).count();
+                ui.label(format!(
+                    "{} of {} selected projects change",
+                    changed,
+                    changes.len()
+                ));
+                egui::ScrollArea::vertical()
+                    .id_source("bulk_edit_preview")
+                    .max_height(250.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("bulk_edit_preview_grid")
+                            .striped(true)
+                            .num_columns(3)
+                            .show(ui, |ui| {
+                                for change in &changes {
+                                    ui.label(format!("{} / {}", change.group, change.project));
+                                    let shown = |value: &Option<String>| match value {
+                                        Some(value) if value.is_empty() => "(empty)".to_string(),
+                                        Some(value) => {
+                                            value.lines().next().unwrap_or_default().to_string()
+                                        }
+                                        None => "(not set)".to_string(),
+                                    };
+                                    match &change.after {
+                                        Err(reason) => {
+                                            ui.label("");
+                                            ui.colored_label(
+                                                Color32::GRAY,
+                                                format!("skipped, {}", reason),
+                                            );
+                                        }
+                                        Ok(after) if *after == change.before => {
+                                            ui.label(shown(&change.before));
+                                            ui.colored_label(Color32::GRAY, "unchanged");
+                                        }
+                                        Ok(after) => {
+                                            ui.colored_label(
+                                                Color32::LIGHT_RED,
+                                                shown(&change.before),
+                                            );
+                                            ui.colored_label(Color32::LIGHT_GREEN, shown(after));
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(changed > 0, egui::Button::new("Apply"))
+                        .clicked()
+                    {
+                        action = Confirm::Apply;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Confirm::Cancel;
+                    }
+                });
+            });
+        if !open {
+            action = Confirm::Cancel;
+        }
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Package;
+
+    fn edit(operation: Operation, value: &str) -> Edit {
+        Edit {
+            field: Field::Overview,
+            operation,
+            value: value.to_string(),
+            find: "old".to_string(),
+        }
+    }
+
+    fn edited(operation: Operation, value: &str, current: &str) -> Option<String> {
+        edit(operation, value).edited(Some(current))
+    }
+
+    #[test]
+    fn every_operation() {
+        let text = "  Old and old  ";
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(edited(Operation::Set, "new", text), some("new"));
+        assert_eq!(edited(Operation::Clear, "new", text), some(""));
+        assert_eq!(
+            edited(Operation::Prefix, ">", text),
+            some(">  Old and old  ")
+        );
+        assert_eq!(
+            edited(Operation::Suffix, "<", text),
+            some("  Old and old  <")
+        );
+        assert_eq!(
+            edited(Operation::Replace, "new", text),
+            some("  Old and new  ")
+        );
+        assert_eq!(edited(Operation::Trim, "", text), some("Old and old"));
+        assert_eq!(
+            edited(Operation::Lowercase, "", text),
+            some("  old and old  ")
+        );
+        assert_eq!(
+            edited(Operation::Uppercase, "", text),
+            some("  OLD AND OLD  ")
+        );
+
+        let mut no_find = edit(Operation::Replace, "new");
+        no_find.find.clear();
+        assert_eq!(no_find.edited(Some(text)), some(text));
+    }
+
+    #[test]
+    fn unset_fields() {
+        let page_background = |operation, value: &str| Edit {
+            field: Field::PageBackground,
+            ..edit(operation, value)
+        };
+        assert_eq!(
+            page_background(Operation::Clear, "").edited(Some("https://x")),
+            None
+        );
+        assert_eq!(page_background(Operation::Trim, "").edited(None), None);
+        assert_eq!(
+            page_background(Operation::Prefix, "https://x/").edited(None),
+            Some("https://x/".to_string())
+        );
+    }
+
+    fn groups() -> HashMap<String, GroupData> {
+        let group = |name: &str, projects: &[&str]| {
+            let mut group = GroupData::new(name.to_string());
+            for project in projects {
+                let mut project = Project::new(project.to_string());
+                project.overview = format!("{} overview", project.name);
+                group.projects.push(project);
+            }
+            (name.to_string(), group)
+        };
+        let mut groups = HashMap::from([group("a", &["p", "q"]), group("b", &["r"])]);
+        groups.get_mut("b").unwrap().projects[0].package = Some(Package {
+            owner: "me".to_string(),
+            ..Package::default()
+        });
+        groups
+    }
+
+    #[test]
+    fn preview_and_apply_across_groups() {
+        let mut groups = groups();
+        let mut window = BulkEditWindow::new("a", Some("q"));
+        window.selection.insert(("b".to_string(), "r".to_string()));
+        window.edit = edit(Operation::Suffix, "!");
+
+        let changes = window.preview(&groups);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.group.as_str(), c.project.as_str(), c.after.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a", "q", Ok(Some("q overview!".to_string()))),
+                ("b", "r", Ok(Some("r overview!".to_string()))),
+            ]
+        );
+        assert!(changes.iter().all(Change::changes));
+
+        window.apply(&mut groups);
+        assert_eq!(groups["a"].projects[0].overview, "p overview");
+        assert_eq!(groups["a"].projects[1].overview, "q overview!");
+        assert_eq!(groups["b"].projects[0].overview, "r overview!");
+
+        // Projects without a package are skipped for package fields.
+        window.selection.insert(("a".to_string(), "p".to_string()));
+        window.edit = Edit {
+            field: Field::PackageOwner,
+            ..edit(Operation::Uppercase, "")
+        };
+        let changes = window.preview(&groups);
+        assert_eq!(changes[0].after, Err("no package"));
+        assert!(!changes[0].changes());
+        window.apply(&mut groups);
+        assert!(groups["a"].projects[0].package.is_none());
+        assert_eq!(
+            groups["b"].projects[0].package.as_ref().unwrap().owner,
+            "ME"
+        );
+    }
+
+    #[test]
+    fn selection_follows_projects_by_name() {
+        let mut groups = groups();
+        let mut window = BulkEditWindow::new("a", Some("q"));
+        window.edit = edit(Operation::Set, "edited");
+        // `q` moves to the front after it was selected.
+        groups.get_mut("a").unwrap().projects.reverse();
+        let changes = window.preview(&groups);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].project.as_str(), changes[0].index), ("q", 0));
+        window.apply(&mut groups);
+        assert_eq!(groups["a"].projects[0].overview, "edited");
+        assert_eq!(groups["a"].projects[1].overview, "p overview");
+
+        groups.get_mut("a").unwrap().projects.remove(0);
+        assert!(window.preview(&groups).is_empty());
+    }
+}
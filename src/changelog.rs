@@ -0,0 +1,380 @@
+use eframe::egui::{self, Color32};
+
+/// The kinds of change an entry can list, in the order they are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Added,
+    Changed,
+    Fixed,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 3] = [Kind::Added, Kind::Changed, Kind::Fixed];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::Added => "Added",
+            Kind::Changed => "Changed",
+            Kind::Fixed => "Fixed",
+        }
+    }
+
+    fn from_heading(heading: &str) -> Option<Kind> {
+        Kind::ALL
+            .into_iter()
+            .find(|kind| kind.label().eq_ignore_ascii_case(heading.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub version: String,
+    pub date: String,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub fixed: Vec<String>,
+    /// Lines under the entry that aren't in a recognised section, kept so
+    /// hand-written notes survive a round trip.
+    pub notes: Vec<String>,
+    /// The text the entry was parsed from, written back as it was for as
+    /// long as the entry isn't edited.
+    source: String,
+}
+
+impl Entry {
+    pub fn new(version: &str, date: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            date: date.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn items(&self, kind: Kind) -> &Vec<String> {
+        match kind {
+            Kind::Added => &self.added,
+            Kind::Changed => &self.changed,
+            Kind::Fixed => &self.fixed,
+        }
+    }
+
+    pub fn items_mut(&mut self, kind: Kind) -> &mut Vec<String> {
+        match kind {
+            Kind::Added => &mut self.added,
+            Kind::Changed => &mut self.changed,
+            Kind::Fixed => &mut self.fixed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        Kind::ALL.iter().all(|kind| self.items(*kind).is_empty()) && self.notes.is_empty()
+    }
+
+    /// Whether the entry still reads the same as the text it came from.
+    fn is_unchanged(&self) -> bool {
+        !self.source.is_empty() && Changelog::parse(&self.source).entries.first() == Some(self)
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = String::from("## ");
+        out.push_str(&self.version);
+        if !self.date.is_empty() {
+            out.push_str(" - ");
+            out.push_str(&self.date);
+        }
+        out.push('\n');
+        for kind in Kind::ALL {
+            let items = self.items(kind);
+            if items.is_empty() {
+                continue;
+            }
+            out.push_str("### ");
+            out.push_str(kind.label());
+            out.push('\n');
+            for item in items {
+                out.push_str("- ");
+                out.push_str(&item.replace('\n', "\n  "));
+                out.push('\n');
+            }
+        }
+        for note in &self.notes {
+            out.push_str(note);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// `Project.changelog` split into per-version entries. It is written as
+/// Markdown so the site renders it unchanged:
+///
+/// ```text
+/// ## 1.3.0 - 2024-05-01
+/// ### Added
+/// - New livery
+/// ### Fixed
+/// - Autopilot disconnect
+/// ```
+///
+/// Text that isn't part of an edited entry is written back exactly as it
+/// was read, so adding an entry only inserts that entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changelog {
+    /// Anything before the first entry.
+    pub preamble: String,
+    pub entries: Vec<Entry>,
+}
+
+impl Changelog {
+    pub fn parse(text: &str) -> Self {
+        let mut changelog = Changelog::default();
+        let mut section: Option<Kind> = None;
+        let mut entry_start = None;
+        let mut offset = 0;
+
+        for raw in text.split_inclusive('\n') {
+            let start = offset;
+            offset += raw.len();
+            let line = raw.trim_end_matches(['\n', '\r']);
+            if let Some(heading) = line.strip_prefix("## ") {
+                match entry_start.replace(start) {
+                    Some(previous) => {
+                        changelog.entries.last_mut().unwrap().source =
+                            text[previous..start].to_string();
+                    }
+                    None => changelog.preamble = text[..start].to_string(),
+                }
+                let (version, date) = parse_heading(heading);
+                changelog.entries.push(Entry::new(&version, &date));
+                section = None;
+                continue;
+            }
+            let Some(entry) = changelog.entries.last_mut() else {
+                continue;
+            };
+            if let Some(heading) = line.strip_prefix("### ") {
+                section = Kind::from_heading(heading);
+                if section.is_none() {
+                    entry.notes.push(line.to_string());
+                }
+                continue;
+            }
+            let item = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "));
+            match (section, item) {
+                (Some(kind), Some(item)) => entry.items_mut(kind).push(item.to_string()),
+                (Some(kind), None) if line.starts_with("  ") => {
+                    if let Some(last) = entry.items_mut(kind).last_mut() {
+                        last.push('\n');
+                        last.push_str(line.trim());
+                    }
+                }
+                _ if line.trim().is_empty() => {}
+                _ => entry.notes.push(line.to_string()),
+            }
+        }
+
+        match entry_start {
+            Some(start) => changelog.entries.last_mut().unwrap().source = text[start..].to_string(),
+            None => changelog.preamble = text.to_string(),
+        }
+        changelog
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = self.preamble.clone();
+        let mut last_written = false;
+        for entry in &self.entries {
+            last_written = !entry.is_unchanged();
+            if last_written {
+                // Written entries are set off by a blank line.
+                while !out.is_empty() && !out.ends_with("\n\n") && !out.ends_with("\n\r\n") {
+                    out.push('\n');
+                }
+                out.push_str(&entry.to_markdown());
+                out.push('\n');
+            } else {
+                // Only needed when entries were removed or moved and the one
+                // that ended the text now has something after it.
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push_str("\n\n");
+                }
+                out.push_str(&entry.source);
+            }
+        }
+        if last_written {
+            out.truncate(out.trim_end_matches('\n').len());
+        }
+        out
+    }
+
+    pub fn entry(&self, version: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.version == version)
+    }
+
+    pub fn entry_mut(&mut self, version: &str) -> Option<&mut Entry> {
+        self.entries.iter_mut().find(|e| e.version == version)
+    }
+
+    /// Adds an empty entry for `version` at the top, unless there already is
+    /// one. An empty entry left over from an earlier bump is replaced.
+    pub fn start_entry(&mut self, version: &str, date: &str) {
+        if self.entry(version).is_some() {
+            return;
+        }
+        if self.entries.first().is_some_and(Entry::is_empty) {
+            self.entries.remove(0);
+        }
+        self.entries.insert(0, Entry::new(version, date));
+    }
+}
+
+/// Splits `[1.3.0] - 2024-05-01` or `1.3.0 (2024-05-01)` into version and date.
+fn parse_heading(heading: &str) -> (String, String) {
+    let heading = heading.trim();
+    let (version, date) = match heading.split_once(" - ") {
+        Some((version, date)) => (version, date),
+        None => match heading.split_once(" (") {
+            Some((version, date)) => (version, date.trim_end_matches(')')),
+            None => (heading, ""),
+        },
+    };
+    (
+        version.trim().trim_matches(['[', ']']).to_string(),
+        date.trim().to_string(),
+    )
+}
+
+/// Structured editor for a changelog string. Edits are written straight
+/// back to `text`.
+pub fn editor_ui(ui: &mut egui::Ui, text: &mut String) {
+    let original = Changelog::parse(text);
+    let mut changelog = original.clone();
+
+    if changelog.entries.is_empty() && !changelog.preamble.trim().is_empty() {
+        ui.colored_label(
+            Color32::YELLOW,
+            "No version entries found, the existing text is kept above the first entry",
+        );
+    }
+
+    let mut remove_entry = None;
+    for (index, entry) in changelog.entries.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Version");
+                    ui.add(egui::TextEdit::singleline(&mut entry.version).desired_width(80.0));
+                    ui.label("Date");
+                    ui.add(egui::TextEdit::singleline(&mut entry.date).desired_width(90.0));
+                    if ui.button("Remove entry").clicked() {
+                        remove_entry = Some(index);
+                    }
+                });
+                for kind in Kind::ALL {
+                    let items = entry.items_mut(kind);
+                    ui.push_id(kind.label(), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(kind.label());
+                            if ui.small_button("+").clicked() {
+                                items.push(String::new());
+                            }
+                        });
+                        let mut remove_item = None;
+                        for (item_index, item) in items.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label("•");
+                                ui.push_id(item_index, |ui| {
+                                    ui.add(egui::TextEdit::singleline(item).desired_width(400.0));
+                                });
+                                if ui.small_button("🗑").clicked() {
+                                    remove_item = Some(item_index);
+                                }
+                            });
+                        }
+                        if let Some(item_index) = remove_item {
+                            items.remove(item_index);
+                        }
+                    });
+                }
+                for note in &entry.notes {
+                    ui.label(note);
+                }
+            });
+        });
+    }
+    if let Some(index) = remove_entry {
+        changelog.entries.remove(index);
+    }
+    if ui.button("Add entry").clicked() {
+        changelog.entries.insert(0, Entry::default());
+    }
+
+    if changelog != original {
+        *text = changelog.to_markdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = concat!(
+        "Changes to the aircraft.\r\n",
+        "\r\n",
+        "## [1.2.0] - 2024-05-01\r\n",
+        "### Added\r\n",
+        "* New livery\r\n",
+        "\r\n",
+        "* Cabin lighting\r\n",
+        "  with dimming\r\n",
+        "### Known issues\r\n",
+        "Some text\r\n",
+        "\r\n",
+        "\r\n",
+        "## 1.1.0 (2024-01-02)\r\n",
+        "- Fixed the autopilot\r\n",
+    );
+
+    #[test]
+    fn round_trip_keeps_text() {
+        let changelog = Changelog::parse(TEXT);
+        assert_eq!(changelog.entries.len(), 2);
+        assert_eq!(
+            changelog.entries[0].added,
+            ["New livery", "Cabin lighting\nwith dimming"]
+        );
+        assert_eq!(changelog.to_markdown(), TEXT);
+
+        let text = TEXT.replace("\r\n", "\n");
+        assert_eq!(Changelog::parse(&text).to_markdown(), text);
+        assert_eq!(
+            Changelog::parse("No entries yet\n").to_markdown(),
+            "No entries yet\n"
+        );
+    }
+
+    #[test]
+    fn start_entry_only_inserts() {
+        let mut changelog = Changelog::parse(TEXT);
+        changelog.start_entry("1.3.0", "2024-06-01");
+        let (preamble, rest) = TEXT.split_at(TEXT.find("## ").unwrap());
+        assert_eq!(
+            changelog.to_markdown(),
+            format!("{}## 1.3.0 - 2024-06-01\n\n{}", preamble, rest)
+        );
+
+        let mut changelog = Changelog::parse("");
+        changelog.start_entry("1.0.0", "2024-06-01");
+        assert_eq!(changelog.to_markdown(), "## 1.0.0 - 2024-06-01");
+    }
+
+    #[test]
+    fn edited_entry_is_rewritten() {
+        let mut changelog = Changelog::parse(TEXT);
+        changelog.entries[1].fixed.push("Gear warning".to_string());
+        let markdown = changelog.to_markdown();
+        assert!(markdown.starts_with(&TEXT[..TEXT.find("## 1.1.0").unwrap()]));
+        assert!(markdown
+            .ends_with("## 1.1.0 - 2024-01-02\n### Fixed\n- Gear warning\n- Fixed the autopilot"));
+    }
+}
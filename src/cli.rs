@@ -0,0 +1,491 @@
+use crate::auth;
+use crate::diff::Diff;
+use crate::git;
+use crate::github::GithubClient;
+use crate::image_check::{self, FieldKind};
+use crate::image_import;
+use crate::release_check;
+use crate::schema;
+use crate::settings::Settings;
+use crate::submit;
+use crate::types;
+use crate::validate::{self, Severity};
+
+const USAGE: &str = "usage: group-json-manager [<command> [<args>]]
+
+With no arguments the editor window is opened.
+
+commands:
+    schema [<path>]     write the groups.json JSON Schema to <path> (default: stdout)
+    validate <path>     check a groups.json file against the schema
+    diff <before> <after> [--markdown]
+                        summarise the changes between two groups.json files
+    check-releases <path> [--api-url <url>]
+                        check that every package points to a published release asset
+    check-images <path> check that every logo and background URL serves an image and
+                        that it follows the image rules in the settings
+    import-image <file> --group <name> [--project <name>] [--field <field>]
+                        convert an image to WebP into the assets checkout and print its
+                        URL; field is logo, beta-background, background (default) or
+                        page-background
+    submit <path> [--title <title>] [--api-url <url>]
+                        open a pull request replacing the groups repository's
+                        groups.json with <path>
+    git status|pull|push [--repo <path>]
+    git branch <name> [--repo <path>]
+    git commit [--from <path>] [-m <message>] [--repo <path>]
+                        work with a local clone of the groups repository (default:
+                        the checkout in the settings); commit copies --from into the
+                        clone first and generates a message from the diff
+    login [--token <token>]
+                        store a GitHub token (read from stdin without --token) or
+                        sign in through the browser with the device flow
+    logout              forget the stored GitHub token
+
+GitHub requests use $GITHUB_TOKEN or the stored token. When the keyring is
+unavailable the token file is encrypted with $GROUP_MANAGER_PASSPHRASE.";
+
+/// Release builds use the Windows GUI subsystem, which starts without a
+/// console, so output would go nowhere. Borrow the console of the shell that
+/// started us instead; this does nothing when there is none (e.g. when run
+/// from Explorer) and on other platforms.
+pub fn attach_console() {
+    #[cfg(windows)]
+    unsafe {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+pub fn run(args: &[String]) -> i32 {
+    match args[0].as_str() {
+        "schema" => match args.get(1) {
+            Some(path) => match schema::write_schema(path, Settings::load().date_format()) {
+                Ok(()) => 0,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    1
+                }
+            },
+            None => {
+                println!(
+                    "{}",
+                    schema::groups_schema_json(Settings::load().date_format())
+                );
+                0
+            }
+        },
+        "validate" => match args.get(1) {
+            Some(path) => validate_file(path),
+            None => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "diff" => match (args.get(1), args.get(2)) {
+            (Some(before), Some(after)) => diff_files(before, after, &args[3..]),
+            _ => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "check-releases" => match args.get(1) {
+            Some(path) => check_releases(path, &args[2..]),
+            None => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "check-images" => match args.get(1) {
+            Some(path) => check_images(path),
+            None => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "import-image" => match args.get(1) {
+            Some(path) => import_image(path, &args[2..]),
+            None => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "submit" => match args.get(1) {
+            Some(path) => submit_file(path, &args[2..]),
+            None => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "git" => git_command(&args[1..]),
+        "login" => login(&args[1..]),
+        "logout" => match auth::forget_token() {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("{}", e);
+                1
+            }
+        },
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            0
+        }
+        other => {
+            eprintln!("unknown command: {}\n\n{}", other, USAGE);
+            2
+        }
+    }
+}
+
+/// Value of `--name <value>` in `args`, if present.
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
+}
+
+fn read_report(path: &str) -> Result<validate::LoadReport, String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|e| format!("Error reading {}: {}", path, e))?;
+    validate::load_groups(&text, Settings::load().date_format())
+        .map_err(|issue| format!("{}:{}", path, issue))
+}
+
+fn validate_file(path: &str) -> i32 {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            return 1;
+        }
+    };
+    match validate::load_groups(&text, Settings::load().date_format()) {
+        Ok(report) => {
+            for issue in &report.issues {
+                let level = match issue.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+                println!("{}:{} [{}]", path, issue, level);
+            }
+            println!(
+                "{} groups loaded, {} groups broken",
+                report.groups.len(),
+                report.broken.len()
+            );
+            i32::from(!report.broken.is_empty())
+        }
+        Err(issue) => {
+            eprintln!("{}:{}", path, issue);
+            1
+        }
+    }
+}
+
+fn diff_files(before: &str, after: &str, args: &[String]) -> i32 {
+    let diff = read_report(before).and_then(|before| {
+        read_report(after).map(|after| Diff::new(&before.groups, &after.groups))
+    });
+    match diff {
+        Ok(diff) if args.iter().any(|arg| arg == "--markdown") => {
+            println!("{}", diff.title());
+            println!();
+            println!("{}", diff.to_markdown());
+            0
+        }
+        Ok(diff) => {
+            println!("{}", diff.commit_message());
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn check_releases(path: &str, args: &[String]) -> i32 {
+    let report = match read_report(path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let settings = Settings::load();
+    let client = match github_client(&settings, option(args, "--api-url")) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let checks = runtime.block_on(release_check::check_groups(&client, &report.groups));
+
+    let mut problems = 0;
+    for check in &checks {
+        for finding in &check.findings {
+            let level = if finding.is_problem() {
+                "problem"
+            } else {
+                "ok"
+            };
+            problems += usize::from(finding.is_problem());
+            println!(
+                "{} / {}: {} [{}]",
+                check.group, check.project, finding, level
+            );
+        }
+    }
+    println!("{} packages checked, {} problems", checks.len(), problems);
+    i32::from(problems > 0)
+}
+
+fn check_images(path: &str) -> i32 {
+    let report = match read_report(path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let settings = Settings::load();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let checks = runtime.block_on(image_check::check_groups(
+        image_check::client(),
+        &report.groups,
+        &settings.image_rules,
+    ));
+
+    let (mut broken, mut flagged) = (0, 0);
+    for check in &checks {
+        let level = if check.is_broken() {
+            broken += 1;
+            "broken"
+        } else if !check.problems().is_empty() {
+            flagged += 1;
+            "warning"
+        } else {
+            "ok"
+        };
+        println!("{} [{}]", check, level);
+    }
+    println!(
+        "{} images checked, {} broken, {} with warnings",
+        checks.len(),
+        broken,
+        flagged
+    );
+    i32::from(broken > 0)
+}
+
+fn import_image(path: &str, args: &[String]) -> i32 {
+    let field = option(args, "--field").unwrap_or("background");
+    let kind = FieldKind::ALL
+        .into_iter()
+        .find(|kind| kind.label().replace(' ', "-") == field);
+    let (Some(group), Some(kind)) = (option(args, "--group"), kind) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let target = image_import::Target {
+        group,
+        project: option(args, "--project"),
+        kind,
+    };
+    match image_import::import(std::path::Path::new(path), &Settings::load(), &target) {
+        Ok((written, url)) => {
+            eprintln!("Wrote {}", written.display());
+            println!("{}", url);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn submit_file(path: &str, args: &[String]) -> i32 {
+    let report = match read_report(path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    if !report.broken.is_empty() {
+        eprintln!("{} has broken groups, fix them before submitting", path);
+        return 1;
+    }
+    let settings = Settings::load();
+    let result = github_client(&settings, option(args, "--api-url")).and_then(|client| {
+        let target = submit::Target::from_settings(&settings)?;
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let upstream = submit::fetch_upstream(&client, &target).await?;
+            let mut unreadable: Vec<String> = upstream.broken.into_keys().collect();
+            unreadable.sort();
+            if !unreadable.is_empty() {
+                eprintln!(
+                    "Warning: {} in {}/{} could not be read and may be summarised as new",
+                    unreadable.join(", "),
+                    target.owner,
+                    target.repo
+                );
+            }
+            let (title, body) = submit::summarise(&upstream.groups, &unreadable, &report.groups);
+            let title = option(args, "--title").map_or(title, str::to_string);
+            let text = types::groups_json(&report.groups);
+            let branch = submit::branch_name();
+            submit::submit(&client, &target, &branch, &title, &body, &text).await
+        })
+    });
+    match result {
+        Ok(pull) => {
+            println!("Opened pull request #{}: {}", pull.number, pull.html_url);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn git_command(args: &[String]) -> i32 {
+    let settings = Settings::load();
+    let repo = std::path::PathBuf::from(option(args, "--repo").unwrap_or(&settings.checkout_path));
+    let file = settings.groups_path.trim_start_matches('/');
+    let remote = settings.git_remote.as_str();
+    let token = || {
+        auth::load_token(passphrase().as_deref())
+            .ok()
+            .flatten()
+            .map(|(token, _)| token)
+    };
+    let result = match args.first().map(String::as_str) {
+        Some("status") => git::status(&repo).map(|status| {
+            let mut lines = vec![match (&status.branch, &status.upstream) {
+                (Some(branch), Some(upstream)) => format!(
+                    "On {} tracking {}, {} ahead, {} behind",
+                    branch, upstream, status.ahead, status.behind
+                ),
+                (Some(branch), None) => format!("On {}, no upstream branch", branch),
+                (None, _) => "HEAD detached".to_string(),
+            }];
+            lines.extend(
+                status
+                    .changes
+                    .iter()
+                    .map(|(path, code)| format!("{:>2} {}", code, path)),
+            );
+            lines.join("\n")
+        }),
+        Some("pull") => git::pull(&repo, remote, token()),
+        Some("push") => git::push(&repo, remote, token()),
+        Some("branch") => match args.get(1).filter(|name| !name.starts_with("--")) {
+            Some(name) => {
+                git::create_branch(&repo, name).map(|()| format!("Switched to new branch {}", name))
+            }
+            None => Err(USAGE.to_string()),
+        },
+        Some("commit") => {
+            let copied = match option(args, "--from") {
+                Some(from) => read_report(from).and_then(|report| {
+                    if report.broken.is_empty() {
+                        git::write_groups(&repo, file, &report.groups, &report.broken).map(|_| ())
+                    } else {
+                        Err(format!("{} has broken groups, fix them first", from))
+                    }
+                }),
+                None => Ok(()),
+            };
+            copied
+                .and_then(|()| match option(args, "-m") {
+                    Some(message) => Ok(message.to_string()),
+                    None => git::generated_message(&repo, file),
+                })
+                .and_then(|message| git::commit_file(&repo, file, &message))
+                .map(|oid| format!("Committed {}", oid))
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn passphrase() -> Option<String> {
+    std::env::var(auth::PASSPHRASE_ENV).ok()
+}
+
+/// Client for `api_url` or the configured API, with the stored token.
+fn github_client(settings: &Settings, api_url: Option<&str>) -> Result<GithubClient, String> {
+    let token = auth::load_token(passphrase().as_deref())?;
+    Ok(
+        GithubClient::new(api_url.unwrap_or(&settings.github_api_url))
+            .with_token(token.as_ref().map(|(token, _)| token.as_str())),
+    )
+}
+
+fn login(args: &[String]) -> i32 {
+    let settings = Settings::load();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let token = match option(args, "--token") {
+        Some(token) => Ok(token.to_string()),
+        None if settings.github_client_id.is_empty() => {
+            eprintln!("Paste a personal access token and press enter:");
+            let mut line = String::new();
+            std::io::stdin()
+                .read_line(&mut line)
+                .map(|_| line.trim().to_string())
+                .map_err(|e| format!("Error reading stdin: {}", e))
+        }
+        None => runtime.block_on(async {
+            let code =
+                auth::request_device_code(&settings.github_login_url, &settings.github_client_id)
+                    .await?;
+            eprintln!(
+                "Enter {} at {} to sign in",
+                code.user_code, code.verification_uri
+            );
+            auth::poll_device_token(
+                &settings.github_login_url,
+                &settings.github_client_id,
+                &code,
+            )
+            .await
+        }),
+    };
+    let result = token.and_then(|token| {
+        let source = auth::save_token(&token, passphrase().as_deref())?;
+        let client = GithubClient::new(&settings.github_api_url).with_token(Some(&token));
+        let user = runtime.block_on(client.user())?;
+        Ok((source, user))
+    });
+    match result {
+        Ok((source, user)) => {
+            let login = user.map(|user| user.login).unwrap_or_default();
+            println!(
+                "Signed in as {} (token stored in {})",
+                login,
+                source.label()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use chrono::{DateTime, NaiveDate};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::types::GroupData;
+use crate::validate::escape_segment;
+
+pub const ISO_8601: &str = "%Y-%m-%d";
+
+/// Formats seen in older groups.json entries, tried in order after ISO-8601.
+/// Day-first is tried before month-first, so "03/04/2024" reads as 3 April;
+/// `is_ambiguous` tells when the other reading would give a different day.
+const INPUT_FORMATS: &[&str] = &[
+    ISO_8601,
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%d/%m/%Y",
+    "%d.%m.%Y",
+    "%d-%m-%Y",
+    "%m/%d/%Y",
+    "%B %d, %Y",
+    "%B %d %Y",
+    "%b %d, %Y",
+    "%b %d %Y",
+    "%d %B %Y",
+    "%d %b %Y",
+];
+
+/// `Project.date` as written in groups.json. Like `Version`, the text is kept
+/// as entered and parsed on demand so unrecognised dates survive a round trip.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ProjectDate(String);
+
+impl ProjectDate {
+    pub fn raw_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.trim().is_empty()
+    }
+
+    pub fn parse(&self) -> Result<NaiveDate, String> {
+        parse_date(&self.0).ok_or_else(|| format!("\"{}\" is not a recognised date", self.0))
+    }
+
+    /// Like `parse`, but reads the date in `format` first so one already
+    /// written that way is never taken for a different day.
+    pub fn parse_in(&self, format: &str) -> Result<NaiveDate, String> {
+        NaiveDate::parse_from_str(self.0.trim(), format).or_else(|_| self.parse())
+    }
+
+    pub fn set(&mut self, date: NaiveDate, format: &str) {
+        self.0 = date.format(format).to_string();
+    }
+
+    /// Rewrites the date in `format`. Returns whether the text changed.
+    pub fn normalise(&mut self, format: &str) -> Result<bool, String> {
+        let formatted = self.parse_in(format)?.format(format).to_string();
+        if formatted == self.0 {
+            return Ok(false);
+        }
+        self.0 = formatted;
+        Ok(true)
+    }
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    INPUT_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|d| d.date_naive())
+        })
+}
+
+/// Whether `text` reads as different days depending on the order the input
+/// formats are tried in, like "03/04/2024".
+pub fn is_ambiguous(text: &str) -> bool {
+    let text = text.trim();
+    let mut dates = INPUT_FORMATS
+        .iter()
+        .filter_map(|format| NaiveDate::parse_from_str(text, format).ok());
+    dates
+        .next()
+        .is_some_and(|first| dates.any(|other| other != first))
+}
+
+/// The schema `format` for dates written in `date_format`: the standard
+/// "date" for ISO-8601, otherwise the chrono format string itself. Other
+/// validators ignore a format they don't know; ours checks it.
+pub fn schema_format(date_format: &str) -> String {
+    if date_format == ISO_8601 {
+        "date".to_string()
+    } else {
+        date_format.to_string()
+    }
+}
+
+/// Whether `format` is usable as an output format, i.e. produces a date that
+/// reads back as the same day.
+pub fn is_valid_format(format: &str) -> bool {
+    let probe = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+    let mut formatted = String::new();
+    if fmt::write(&mut formatted, format_args!("{}", probe.format(format))).is_err() {
+        return false;
+    }
+    NaiveDate::parse_from_str(&formatted, format).ok() == Some(probe)
+}
+
+impl fmt::Display for ProjectDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl JsonSchema for ProjectDate {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "ProjectDate".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        let date = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: Some("date".to_string()),
+            ..Default::default()
+        };
+        crate::schema::or_empty(date, false)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DateChange {
+    pub group: String,
+    pub project: String,
+    pub pointer: String,
+    pub from: String,
+    pub to: String,
+    /// `from` could also have been read as a different day.
+    pub ambiguous: bool,
+}
+
+/// Rewrites every parseable project date in `format`, returning what changed.
+/// Dates that don't parse are left alone; the loader already reports them.
+pub fn normalise_dates(groups: &mut HashMap<String, GroupData>, format: &str) -> Vec<DateChange> {
+    let mut changes = Vec::new();
+    for (key, group) in groups.iter_mut() {
+        for (index, project) in group.projects.iter_mut().enumerate() {
+            let from = project.date.to_string();
+            let ambiguous =
+                NaiveDate::parse_from_str(from.trim(), format).is_err() && is_ambiguous(&from);
+            if let Ok(true) = project.date.normalise(format) {
+                changes.push(DateChange {
+                    group: key.clone(),
+                    project: project.name.clone(),
+                    pointer: format!("/{}/projects/{}/date", escape_segment(key), index),
+                    from,
+                    to: project.date.to_string(),
+                    ambiguous,
+                });
+            }
+        }
+    }
+    changes.sort_by(|a, b| (&a.group, &a.pointer).cmp(&(&b.group, &b.pointer)));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Project;
+    use crate::validate;
+
+    fn groups_with_date(date: &str) -> HashMap<String, GroupData> {
+        let mut group = GroupData::new("g".to_string());
+        let mut project = Project::new("p".to_string());
+        *project.date.raw_mut() = date.to_string();
+        group.projects.push(project);
+        HashMap::from([("g".to_string(), group)])
+    }
+
+    #[test]
+    fn formats_are_checked_before_use() {
+        assert!(is_valid_format(ISO_8601));
+        assert!(is_valid_format("%d/%m/%Y"));
+        // Loses the year, so it doesn't read back as the same day.
+        assert!(!is_valid_format("%d/%m"));
+        assert!(!is_valid_format("%Q"));
+    }
+
+    #[test]
+    fn schema_follows_the_date_format() {
+        let date_warnings = |text: &str, format: &str| {
+            validate::load_groups(text, format)
+                .unwrap()
+                .issues
+                .into_iter()
+                .filter(|issue| issue.pointer.ends_with("/date"))
+                .count()
+        };
+        let iso = crate::types::groups_json(&groups_with_date("2024-12-31"));
+        let day_first = crate::types::groups_json(&groups_with_date("31/12/2024"));
+        assert_eq!(date_warnings(&iso, ISO_8601), 0);
+        assert_eq!(date_warnings(&day_first, ISO_8601), 1);
+        assert_eq!(date_warnings(&day_first, "%d/%m/%Y"), 0);
+        assert_eq!(date_warnings(&iso, "%d/%m/%Y"), 1);
+
+        assert!(crate::schema::groups_schema_json(ISO_8601).contains(r#""format": "date""#));
+        assert!(crate::schema::groups_schema_json("%d/%m/%Y").contains(r#""format": "%d/%m/%Y""#));
+    }
+
+    #[test]
+    fn ambiguous_dates_are_flagged() {
+        let mut groups = groups_with_date("03/04/2024");
+        let changes = normalise_dates(&mut groups, ISO_8601);
+        assert_eq!(changes[0].to, "2024-04-03");
+        assert!(changes[0].ambiguous);
+
+        let mut groups = groups_with_date("31/12/2024");
+        assert!(!normalise_dates(&mut groups, ISO_8601)[0].ambiguous);
+
+        // A date already in the configured format is read in that format.
+        let mut groups = groups_with_date("03/04/2024");
+        assert!(normalise_dates(&mut groups, "%m/%d/%Y").is_empty());
+        let mut date = groups["g"].projects[0].date.clone();
+        assert_eq!(
+            date.parse_in("%m/%d/%Y"),
+            Ok(NaiveDate::from_ymd_opt(2024, 3, 4).unwrap())
+        );
+        assert_eq!(date.normalise(ISO_8601), Ok(true));
+        assert_eq!(date.to_string(), "2024-04-03");
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::types::{GroupData, Package, Project};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// What happened to one group, with its changes in reading order.
+#[derive(Debug, Clone)]
+pub struct GroupDiff {
+    pub name: String,
+    pub kind: Kind,
+    pub changes: Vec<String>,
+}
+
+impl fmt::Display for GroupDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Added => write!(f, "New group {}", self.name)?,
+            Kind::Removed => write!(f, "Removed group {}", self.name)?,
+            Kind::Changed => write!(f, "Group {}", self.name)?,
+        }
+        if !self.changes.is_empty() {
+            write!(f, ": {}", self.changes.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Human readable differences between two sets of groups, e.g.
+/// "Group X: project Y version 1.2.0 → 1.3.0; new project Z; palette updated".
+#[derive(Debug, Clone, Default)]
+pub struct Diff {
+    pub groups: Vec<GroupDiff>,
+}
+
+impl Diff {
+    pub fn new(before: &HashMap<String, GroupData>, after: &HashMap<String, GroupData>) -> Self {
+        let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+        names.sort();
+        names.dedup();
+        let groups = names
+            .into_iter()
+            .filter_map(|name| {
+                let (kind, changes) = match (before.get(name), after.get(name)) {
+                    (None, Some(new)) => (Kind::Added, new_group(new)),
+                    (Some(_), None) => (Kind::Removed, Vec::new()),
+                    (Some(old), Some(new)) if old != new => {
+                        (Kind::Changed, group_changes(old, new))
+                    }
+                    _ => return None,
+                };
+                Some(GroupDiff {
+                    name: name.clone(),
+                    kind,
+                    changes,
+                })
+            })
+            .collect();
+        Self { groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// One line summary, for commit subjects and pull request titles.
+    pub fn title(&self) -> String {
+        let names: Vec<&str> = self.groups.iter().map(|g| g.name.as_str()).collect();
+        match self.groups.as_slice() {
+            [] => "Update groups.json".to_string(),
+            [group] => match group.kind {
+                Kind::Added => format!("Add {}", group.name),
+                Kind::Removed => format!("Remove {}", group.name),
+                Kind::Changed => format!("Update {}", group.name),
+            },
+            [_, _] => format!("Update {} and {}", names[0], names[1]),
+            _ => format!(
+                "Update {}, {} and {} more",
+                names[0],
+                names[1],
+                names.len() - 2
+            ),
+        }
+    }
+
+    /// Title, blank line and one line per group.
+    pub fn commit_message(&self) -> String {
+        if self.is_empty() {
+            return self.title();
+        }
+        format!("{}\n\n{}", self.title(), self)
+    }
+
+    /// Markdown with a list of changes under each group.
+    pub fn to_markdown(&self) -> String {
+        if self.is_empty() {
+            return "No changes to groups.json.".to_string();
+        }
+        let mut out = String::from("## Changes\n");
+        for group in &self.groups {
+            let heading = match group.kind {
+                Kind::Added => format!("New group **{}**", group.name),
+                Kind::Removed => format!("Removed group **{}**", group.name),
+                Kind::Changed => format!("**{}**", group.name),
+            };
+            out.push_str(&format!("\n- {}", heading));
+            for change in &group.changes {
+                out.push_str(&format!("\n  - {}", change));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self.groups.iter().map(GroupDiff::to_string).collect();
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+fn new_group(group: &GroupData) -> Vec<String> {
+    group
+        .projects
+        .iter()
+        .map(|project| format!("project {} {}", project.name, project.version))
+        .collect()
+}
+
+fn group_changes(old: &GroupData, new: &GroupData) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+        changes.push(format!("renamed {} → {}", old.name, new.name));
+    }
+    for project in &new.projects {
+        match old.projects.iter().find(|p| p.name == project.name) {
+            None => changes.push(format!("new project {} {}", project.name, project.version)),
+            Some(previous) if previous != project => {
+                changes.push(format!(
+                    "project {} {}",
+                    project.name,
+                    project_changes(previous, project).join(", ")
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    for project in &old.projects {
+        if !new.projects.iter().any(|p| p.name == project.name) {
+            changes.push(format!("removed project {}", project.name));
+        }
+    }
+    let order = |group: &GroupData| -> Vec<String> {
+        group.projects.iter().map(|p| p.name.clone()).collect()
+    };
+    let (mut old_order, mut new_order) = (order(old), order(new));
+    old_order.retain(|name| new_order.contains(name));
+    new_order.retain(|name| old_order.contains(name));
+    if old_order != new_order {
+        changes.push("projects reordered".to_string());
+    }
+
+    if old.palette != new.palette {
+        changes.push("palette updated".to_string());
+    }
+    if old.logo != new.logo {
+        changes.push("logo updated".to_string());
+    }
+    if old.beta != new.beta {
+        changes.push("beta background updated".to_string());
+    }
+    if old.path != new.path {
+        changes.push(format!("path {} → {}", old.path, new.path));
+    }
+    if old.hide.unwrap_or(false) != new.hide.unwrap_or(false) {
+        changes.push(
+            if new.hide == Some(true) {
+                "hidden"
+            } else {
+                "shown"
+            }
+            .to_string(),
+        );
+    }
+    if old.update.unwrap_or(false) != new.update.unwrap_or(false) {
+        changes.push(
+            if new.update == Some(true) {
+                "update flag set"
+            } else {
+                "update flag cleared"
+            }
+            .to_string(),
+        );
+    }
+    changes
+}
+
+fn project_changes(old: &Project, new: &Project) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.version != new.version {
+        changes.push(format!("version {} → {}", old.version, new.version));
+    }
+    if old.date != new.date {
+        changes.push(format!("date {} → {}", old.date, new.date));
+    }
+    let updated = [
+        ("changelog", old.changelog != new.changelog),
+        ("overview", old.overview != new.overview),
+        ("description", old.description != new.description),
+        ("background", old.background != new.background),
+        ("page background", old.pageBackground != new.pageBackground),
+        ("variants", old.variants != new.variants),
+    ];
+    changes.extend(
+        updated
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(field, _)| format!("{} updated", field)),
+    );
+    match (&old.package, &new.package) {
+        (None, Some(package)) => changes.push(format!(
+            "package {}/{} added",
+            package.owner, package.repoName
+        )),
+        (Some(_), None) => changes.push("package removed".to_string()),
+        (Some(old), Some(new)) if old != new => changes.extend(package_changes(old, new)),
+        _ => {}
+    }
+    changes
+}
+
+fn package_changes(old: &Package, new: &Package) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.owner != new.owner || old.repoName != new.repoName {
+        changes.push(format!(
+            "package repository {}/{} → {}/{}",
+            old.owner, old.repoName, new.owner, new.repoName
+        ));
+    }
+    if old.version != new.version {
+        changes.push(format!("package version {} → {}", old.version, new.version));
+    }
+    if old.fileName != new.fileName {
+        changes.push(format!("package file {} → {}", old.fileName, new.fileName));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::Version;
+
+    fn project(name: &str, version: &str) -> Project {
+        let mut project = Project::new(name.to_string());
+        project.version = Version::new(version);
+        project
+    }
+
+    fn group(name: &str, projects: Vec<Project>) -> (String, GroupData) {
+        let mut group = GroupData::new(name.to_string());
+        group.projects = projects;
+        (name.to_string(), group)
+    }
+
+    fn before() -> HashMap<String, GroupData> {
+        HashMap::from([
+            group("keep", vec![project("k", "1.0.0")]),
+            group(
+                "change",
+                vec![project("p1", "1.0.0"), project("p2", "2.0.0")],
+            ),
+            group("gone", vec![project("g", "1.0.0")]),
+        ])
+    }
+
+    fn after() -> HashMap<String, GroupData> {
+        let mut groups = before();
+        groups.remove("gone");
+        let change = groups.get_mut("change").unwrap();
+        change.projects[0].version = Version::new("1.1.0");
+        change.projects[0].overview = "New overview".to_string();
+        change.projects.remove(1);
+        change.projects.push(project("p3", "0.1.0"));
+        change.palette.primary = "#ffffff".to_string();
+        change.hide = Some(true);
+        groups.extend([group("fresh", vec![project("n", "1.0.0")])]);
+        groups
+    }
+
+    #[test]
+    fn added_removed_and_changed_groups() {
+        let diff = Diff::new(&before(), &after());
+        let groups: Vec<_> = diff
+            .groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.kind, g.changes.clone()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (
+                    "change",
+                    Kind::Changed,
+                    vec![
+                        "project p1 version 1.0.0 → 1.1.0, overview updated".to_string(),
+                        "new project p3 0.1.0".to_string(),
+                        "removed project p2".to_string(),
+                        "palette updated".to_string(),
+                        "hidden".to_string(),
+                    ]
+                ),
+                ("fresh", Kind::Added, vec!["project n 1.0.0".to_string()]),
+                ("gone", Kind::Removed, vec![]),
+            ]
+        );
+        assert_eq!(diff.title(), "Update change, fresh and 1 more");
+        assert_eq!(
+            diff.to_string(),
+            "Group change: project p1 version 1.0.0 → 1.1.0, overview updated; \
+             new project p3 0.1.0; removed project p2; palette updated; hidden\n\
+             New group fresh: project n 1.0.0\n\
+             Removed group gone"
+        );
+    }
+
+    #[test]
+    fn titles() {
+        let before = before();
+        let mut one = before.clone();
+        one.remove("gone");
+        assert_eq!(Diff::new(&before, &one).title(), "Remove gone");
+        assert_eq!(Diff::new(&one, &before).title(), "Add gone");
+        one.get_mut("keep").unwrap().logo = "https://example.com/logo.png".to_string();
+        assert_eq!(Diff::new(&before, &one).title(), "Update gone and keep");
+
+        let same = Diff::new(&before, &before);
+        assert!(same.is_empty());
+        assert_eq!(same.title(), "Update groups.json");
+        assert_eq!(same.commit_message(), "Update groups.json");
+        assert_eq!(same.to_markdown(), "No changes to groups.json.");
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let diff = Diff::new(&before(), &after());
+        assert_eq!(
+            diff.to_markdown(),
+            "## Changes\n\
+             \n- **change**\
+             \n  - project p1 version 1.0.0 → 1.1.0, overview updated\
+             \n  - new project p3 0.1.0\
+             \n  - removed project p2\
+             \n  - palette updated\
+             \n  - hidden\
+             \n- New group **fresh**\
+             \n  - project n 1.0.0\
+             \n- Removed group **gone**"
+        );
+        assert!(diff
+            .commit_message()
+            .starts_with("Update change, fresh and 1 more\n\nGroup change: "));
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use eframe::egui::{self, Color32, RichText, Sense, Stroke, Vec2};
+
+use crate::image_check::ImageCheck;
+use crate::types::GroupData;
+use crate::widgets;
+
+const CARD_WIDTH: f32 = 220.0;
+
+/// Assets a group is missing or that the last image check found broken.
+fn missing_assets(name: &str, group: &GroupData, image_checks: &[ImageCheck]) -> Vec<String> {
+    let mut missing = Vec::new();
+    if group.logo.trim().is_empty() {
+        missing.push("no logo".to_string());
+    }
+    if group.beta.background.trim().is_empty() {
+        missing.push("no beta background".to_string());
+    }
+    let without_background = group
+        .projects
+        .iter()
+        .filter(|project| project.background.trim().is_empty())
+        .count();
+    if without_background > 0 {
+        missing.push(format!(
+            "{} projects without a background",
+            without_background
+        ));
+    }
+    let broken = image_checks
+        .iter()
+        .filter(|check| check.field.group == name && check.is_broken())
+        .count();
+    if broken > 0 {
+        missing.push(format!("{} broken images", broken));
+    }
+    missing
+}
+
+/// The newest project date in the group, as groups have no date of their own.
+fn last_modified(group: &GroupData) -> Option<NaiveDate> {
+    group
+        .projects
+        .iter()
+        .filter_map(|project| project.date.parse().ok())
+        .max()
+}
+
+/// Every group as a card, to pick one with some idea of what is in it.
+#[derive(Debug, Clone, Default)]
+pub struct Gallery {
+    missing_only: bool,
+}
+
+impl Gallery {
+    /// Returns the name of the group whose card was clicked.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        groups: &HashMap<String, GroupData>,
+        image_checks: &[ImageCheck],
+    ) -> Option<String> {
+        ui.checkbox(&mut self.missing_only, "Only groups with missing assets");
+        let mut names: Vec<&String> = groups.keys().collect();
+        names.sort_by_key(|name| name.to_lowercase());
+
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            for name in names {
+                let group = &groups[name];
+                let missing = missing_assets(name, group, image_checks);
+                if self.missing_only && missing.is_empty() {
+                    continue;
+                }
+                if card(ui, name, group, &missing).clicked() {
+                    clicked = Some(name.clone());
+                }
+            }
+        });
+        clicked
+    }
+}
+
+fn card(ui: &mut egui::Ui, name: &str, group: &GroupData, missing: &[String]) -> egui::Response {
+    let mut frame = egui::Frame::group(ui.style());
+    if !missing.is_empty() {
+        frame = frame.stroke(Stroke::new(1.5, Color32::LIGHT_RED));
+    }
+    let response = frame
+        .show(ui, |ui| {
+            ui.set_width(CARD_WIDTH);
+            ui.set_min_height(150.0);
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    widgets::image_preview(ui, &group.logo, 48.0);
+                    ui.vertical(|ui| {
+                        ui.strong(&group.name);
+                        if group.name != name {
+                            ui.small(name);
+                        }
+                    });
+                });
+                ui.horizontal(|ui| {
+                    for hex in [&group.palette.primary, &group.palette.secondary] {
+                        match widgets::parse_colour(hex) {
+                            Some(colour) => {
+                                egui::widgets::color_picker::show_color(
+                                    ui,
+                                    colour,
+                                    Vec2::new(24.0, 16.0),
+                                );
+                            }
+                            None => {
+                                ui.colored_label(Color32::LIGHT_RED, "no colour");
+                            }
+                        }
+                    }
+                });
+                ui.label(format!("{} projects", group.projects.len()));
+                let mut flags = Vec::new();
+                if group.hide == Some(true) {
+                    flags.push("hidden");
+                }
+                if group.update == Some(true) {
+                    flags.push("update");
+                }
+                if !flags.is_empty() {
+                    ui.label(RichText::new(flags.join(", ")).italics());
+                }
+                match last_modified(group) {
+                    Some(date) => ui.small(format!("Last project update {}", date)),
+                    None => ui.small("No dated projects"),
+                };
+                for problem in missing {
+                    ui.colored_label(Color32::LIGHT_RED, problem);
+                }
+            });
+        })
+        .response;
+    response
+        .interact(Sense::click())
+        .on_hover_text("Open this group")
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+}
@@ -0,0 +1,600 @@
+use eframe::egui::{self, Color32};
+use git2::build::CheckoutBuilder;
+use git2::{
+    BranchType, Cred, CredentialType, FetchOptions, PushOptions, RemoteCallbacks, Repository,
+    Signature, Status as FileStatus, StatusOptions,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::date;
+use crate::diff::Diff;
+use crate::settings::Settings;
+use crate::task::{self, SharedTask, Task};
+use crate::types::{self, GroupData};
+use crate::validate;
+
+/// State of a local clone of the groups repository.
+#[derive(Debug, Clone)]
+pub struct Status {
+    /// `None` when HEAD is detached.
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Changed paths with a short git-style code (`M`, `A`, `D`, `??`).
+    pub changes: Vec<(String, &'static str)>,
+}
+
+fn open(path: &Path) -> Result<Repository, String> {
+    Repository::open(path).map_err(|e| format!("Error opening {}: {}", path.display(), e))
+}
+
+fn git_error(action: &str) -> impl Fn(git2::Error) -> String + '_ {
+    move |e| format!("Error {}: {}", action, e.message())
+}
+
+fn current_branch(repo: &Repository) -> Result<String, String> {
+    let head = repo.head().map_err(git_error("reading HEAD"))?;
+    if !head.is_branch() {
+        return Err("HEAD is detached, create a branch first".to_string());
+    }
+    head.shorthand()
+        .map(str::to_string)
+        .ok_or_else(|| "The branch name is not UTF-8".to_string())
+}
+
+fn status_code(status: FileStatus) -> &'static str {
+    if status.is_wt_new() {
+        "??"
+    } else if status.is_index_new() {
+        "A"
+    } else if status.is_index_deleted() || status.is_wt_deleted() {
+        "D"
+    } else if status.is_conflicted() {
+        "U"
+    } else {
+        "M"
+    }
+}
+
+pub fn status(path: &Path) -> Result<Status, String> {
+    let repo = open(path)?;
+    let branch = match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Ok(_) => None,
+        // A fresh clone of an empty repository has an unborn branch that
+        // HEAD only points at symbolically.
+        Err(_) => repo.find_reference("HEAD").ok().and_then(|head| {
+            head.symbolic_target()
+                .and_then(|target| target.strip_prefix("refs/heads/"))
+                .map(str::to_string)
+        }),
+    };
+
+    let (mut upstream, mut ahead, mut behind) = (None, 0, 0);
+    if let Some(name) = &branch {
+        if let Ok(local) = repo.find_branch(name, BranchType::Local) {
+            if let Ok(remote) = local.upstream() {
+                upstream = remote.name().ok().flatten().map(str::to_string);
+                if let (Some(local), Some(remote)) = (local.get().target(), remote.get().target()) {
+                    (ahead, behind) = repo
+                        .graph_ahead_behind(local, remote)
+                        .map_err(git_error("comparing with upstream"))?;
+                }
+            }
+        }
+    }
+
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let changes = repo
+        .statuses(Some(&mut options))
+        .map_err(git_error("reading status"))?
+        .iter()
+        .filter(|entry| !entry.status().is_ignored())
+        .filter_map(|entry| Some((entry.path()?.to_string(), status_code(entry.status()))))
+        .collect();
+
+    Ok(Status {
+        branch,
+        upstream,
+        ahead,
+        behind,
+        changes,
+    })
+}
+
+/// Creates `name` at HEAD and switches to it. The working tree is left
+/// alone, so uncommitted edits carry over to the new branch.
+pub fn create_branch(path: &Path, name: &str) -> Result<(), String> {
+    let repo = open(path)?;
+    let commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(git_error("reading HEAD"))?;
+    repo.branch(name, &commit, false)
+        .map_err(git_error("creating branch"))?;
+    repo.set_head(&format!("refs/heads/{}", name))
+        .map_err(git_error("switching branch"))
+}
+
+/// groups.json as committed at HEAD, `None` if it isn't in the repository.
+pub fn head_groups(path: &Path, file: &str) -> Result<Option<HashMap<String, GroupData>>, String> {
+    let repo = open(path)?;
+    let Ok(tree) = repo.head().and_then(|head| head.peel_to_tree()) else {
+        return Ok(None);
+    };
+    let Ok(entry) = tree.get_path(Path::new(file)) else {
+        return Ok(None);
+    };
+    let blob = repo
+        .find_blob(entry.id())
+        .map_err(git_error("reading groups.json"))?;
+    let text = std::str::from_utf8(blob.content())
+        .map_err(|e| format!("Error reading {}: {}", file, e))?;
+    validate::load_groups(text, date::ISO_8601)
+        .map(|report| Some(report.groups))
+        .map_err(|e| format!("{} at HEAD: {}", file, e))
+}
+
+/// Writes `groups`, and the raw JSON of the `broken` ones, into the
+/// checkout the same way saving does.
+pub fn write_groups(
+    path: &Path,
+    file: &str,
+    groups: &HashMap<String, GroupData>,
+    broken: &HashMap<String, String>,
+) -> Result<PathBuf, String> {
+    let target = path.join(file);
+    std::fs::write(&target, types::document_json(groups, broken))
+        .map_err(|e| format!("Error writing {}: {}", target.display(), e))?;
+    Ok(target)
+}
+
+/// Commit message describing how the checkout's `file` differs from HEAD.
+pub fn generated_message(path: &Path, file: &str) -> Result<String, String> {
+    let before = head_groups(path, file)?.unwrap_or_default();
+    let text = std::fs::read_to_string(path.join(file))
+        .map_err(|e| format!("Error reading {}: {}", file, e))?;
+    let after = validate::load_groups(&text, date::ISO_8601)
+        .map_err(|e| e.to_string())?
+        .groups;
+    Ok(Diff::new(&before, &after).commit_message())
+}
+
+/// Stages `file` and commits it on the current branch.
+pub fn commit_file(path: &Path, file: &str, message: &str) -> Result<String, String> {
+    let repo = open(path)?;
+    let mut index = repo.index().map_err(git_error("reading the index"))?;
+    index
+        .add_path(Path::new(file))
+        .map_err(git_error("staging"))?;
+    index.write().map_err(git_error("writing the index"))?;
+    let tree_id = index.write_tree().map_err(git_error("writing the tree"))?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(git_error("writing the tree"))?;
+
+    let parent = repo.head().and_then(|head| head.peel_to_commit()).ok();
+    if parent
+        .as_ref()
+        .is_some_and(|parent| parent.tree_id() == tree_id)
+    {
+        return Err(format!("{} has no changes to commit", file));
+    }
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("Group Manager", "group-manager@localhost"))
+        .map_err(git_error("creating a signature"))?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let oid = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .map_err(git_error("committing"))?;
+    Ok(oid.to_string())
+}
+
+/// Authenticates https remotes on github.com with the GitHub token, other
+/// https remotes with the user's git credential helper and ssh remotes with
+/// the ssh agent. The token is never sent to any other host.
+fn callbacks(token: Option<String>) -> RemoteCallbacks<'static> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempts = 0;
+    callbacks.credentials(move |url, username, allowed| {
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("authentication failed"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(token) = token.as_ref().filter(|_| is_github(url)) {
+                return Cred::userpass_plaintext("x-access-token", token);
+            }
+            let config = git2::Config::open_default()?;
+            return Cred::credential_helper(&config, url, username);
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        Cred::default()
+    });
+    callbacks
+}
+
+/// Whether `url` is an https remote on github.com.
+fn is_github(url: &str) -> bool {
+    let Some(rest) = url.strip_prefix("https://") else {
+        return false;
+    };
+    let authority = rest.split('/').next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    host.eq_ignore_ascii_case("github.com")
+}
+
+/// Fetches the current branch from `remote` and fast-forwards to it.
+pub fn pull(path: &Path, remote: &str, token: Option<String>) -> Result<String, String> {
+    let repo = open(path)?;
+    let branch = current_branch(&repo)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks(token));
+    repo.find_remote(remote)
+        .map_err(git_error("finding the remote"))?
+        .fetch(&[&branch], Some(&mut options), None)
+        .map_err(git_error("fetching"))?;
+
+    let fetched = repo
+        .find_reference("FETCH_HEAD")
+        .and_then(|reference| repo.reference_to_annotated_commit(&reference))
+        .map_err(git_error("reading FETCH_HEAD"))?;
+    let (analysis, _) = repo
+        .merge_analysis(&[&fetched])
+        .map_err(git_error("comparing with the remote"))?;
+    if analysis.is_up_to_date() {
+        return Ok("Already up to date".to_string());
+    }
+    if !analysis.is_fast_forward() {
+        return Err(format!(
+            "{} has diverged from {}/{}, merge it with git",
+            branch, remote, branch
+        ));
+    }
+    // Check out first so local edits that would be overwritten stop the pull.
+    let commit = repo
+        .find_object(fetched.id(), None)
+        .map_err(git_error("reading the fetched commit"))?;
+    repo.checkout_tree(&commit, Some(CheckoutBuilder::new().safe()))
+        .map_err(git_error("checking out"))?;
+    repo.find_reference(&format!("refs/heads/{}", branch))
+        .and_then(|mut reference| reference.set_target(fetched.id(), "pull: fast-forward"))
+        .map_err(git_error("updating the branch"))?;
+    Ok(format!("Fast-forwarded {} to {:.7}", branch, fetched.id()))
+}
+
+/// Pushes the current branch to `remote` and sets it as the upstream.
+pub fn push(path: &Path, remote: &str, token: Option<String>) -> Result<String, String> {
+    let repo = open(path)?;
+    let branch = current_branch(&repo)?;
+    let mut rejected = None;
+    let mut callbacks = callbacks(token);
+    callbacks.push_update_reference(|_, status| {
+        rejected = status.map(str::to_string);
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    repo.find_remote(remote)
+        .map_err(git_error("finding the remote"))?
+        .push(
+            &[format!("refs/heads/{0}:refs/heads/{0}", branch)],
+            Some(&mut options),
+        )
+        .map_err(git_error("pushing"))?;
+    drop(options);
+    if let Some(reason) = rejected {
+        return Err(format!("{} rejected the push: {}", remote, reason));
+    }
+    if let Ok(mut local) = repo.find_branch(&branch, BranchType::Local) {
+        let _ = local.set_upstream(Some(&format!("{}/{}", remote, branch)));
+    }
+    Ok(format!("Pushed {} to {}", branch, remote))
+}
+
+/// What the checkout panel wants the app to do with the loaded groups.
+pub enum Action {
+    None,
+    /// Replace the loaded groups with the checkout's groups.json.
+    Load(PathBuf),
+}
+
+/// The "Local checkout" section.
+#[derive(Debug, Clone, Default)]
+pub struct CheckoutPanel {
+    status: Option<Result<Status, String>>,
+    branch_name: String,
+    message: String,
+    outcome: Option<Result<String, String>>,
+    network: SharedTask<Result<String, String>>,
+}
+
+impl CheckoutPanel {
+    fn refresh(&mut self, path: &Path) {
+        self.status = Some(status(path));
+    }
+
+    fn report(&mut self, path: &Path, outcome: Result<String, String>) {
+        self.outcome = Some(outcome);
+        self.refresh(path);
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        settings: &Settings,
+        groups: &HashMap<String, GroupData>,
+        broken: &HashMap<String, String>,
+        token: Option<&str>,
+    ) -> Action {
+        let mut action = Action::None;
+        let path = PathBuf::from(settings.checkout_path.trim());
+        let file = settings.groups_path.trim().trim_start_matches('/');
+        let remote = settings.git_remote.trim();
+
+        let finished = match &*self.network.lock().unwrap() {
+            Task::Done(outcome) => Some(outcome.clone()),
+            _ => None,
+        };
+        if let Some(outcome) = finished {
+            *self.network.lock().unwrap() = Task::Idle;
+            self.report(&path, outcome);
+        }
+
+        egui::CollapsingHeader::new("Local checkout")
+            .id_source("local_checkout")
+            .show(ui, |ui| {
+                if settings.checkout_path.trim().is_empty() {
+                    ui.label("Set the path of a clone of the groups repository in the settings");
+                    return;
+                }
+                if self.status.is_none() {
+                    self.refresh(&path);
+                }
+                ui.horizontal(|ui| {
+                    ui.label(path.display().to_string());
+                    if ui.button("Refresh").clicked() {
+                        self.refresh(&path);
+                    }
+                });
+                match &self.status {
+                    Some(Ok(status)) => {
+                        let branch = status.branch.as_deref().unwrap_or("(detached HEAD)");
+                        match &status.upstream {
+                            Some(upstream) => ui.label(format!(
+                                "On {} tracking {}, {} ahead, {} behind",
+                                branch, upstream, status.ahead, status.behind
+                            )),
+                            None => ui.label(format!("On {}, no upstream branch", branch)),
+                        };
+                        if status.changes.is_empty() {
+                            ui.label("Working tree clean");
+                        }
+                        for (changed, code) in &status.changes {
+                            ui.monospace(format!("{:>2} {}", code, changed));
+                        }
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                        return;
+                    }
+                    None => {}
+                }
+
+                let busy = self.network.lock().unwrap().is_running();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!busy, egui::Button::new("Pull")).clicked() {
+                        let (path, remote) = (path.clone(), remote.to_string());
+                        let token = token.map(str::to_string);
+                        task::spawn(ui.ctx(), &self.network, async move {
+                            pull(&path, &remote, token)
+                        });
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("Push")).clicked() {
+                        let (path, remote) = (path.clone(), remote.to_string());
+                        let token = token.map(str::to_string);
+                        task::spawn(ui.ctx(), &self.network, async move {
+                            push(&path, &remote, token)
+                        });
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
+                    if ui.button(format!("Load {} from checkout", file)).clicked() {
+                        action = Action::Load(path.join(file));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("New branch");
+                    ui.text_edit_singleline(&mut self.branch_name);
+                    let name = self.branch_name.trim().to_string();
+                    if ui
+                        .add_enabled(!name.is_empty(), egui::Button::new("Create"))
+                        .clicked()
+                    {
+                        let outcome = create_branch(&path, &name)
+                            .map(|()| format!("Switched to new branch {}", name));
+                        if outcome.is_ok() {
+                            self.branch_name.clear();
+                        }
+                        self.report(&path, outcome);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button(format!("Write {} to checkout", file)).clicked() {
+                        let outcome = write_groups(&path, file, groups, broken)
+                            .map(|written| format!("Wrote {}", written.display()));
+                        if outcome.is_ok() {
+                            if let Ok(message) = generated_message(&path, file) {
+                                self.message = message;
+                            }
+                        }
+                        self.report(&path, outcome);
+                    }
+                    if ui.button("Generate message").clicked() {
+                        match generated_message(&path, file) {
+                            Ok(message) => self.message = message,
+                            Err(e) => self.outcome = Some(Err(e)),
+                        }
+                    }
+                });
+                ui.label("Commit message");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.message)
+                        .desired_rows(4)
+                        .desired_width(f32::INFINITY),
+                );
+                if ui
+                    .add_enabled(
+                        !self.message.trim().is_empty(),
+                        egui::Button::new(format!("Commit {}", file)),
+                    )
+                    .clicked()
+                {
+                    let outcome = commit_file(&path, file, self.message.trim())
+                        .map(|oid| format!("Committed {:.7}", oid));
+                    if outcome.is_ok() {
+                        self.message.clear();
+                    }
+                    self.report(&path, outcome);
+                }
+
+                match &self.outcome {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                    }
+                    None => {}
+                }
+            });
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const FILE: &str = "groups.json";
+
+    /// A bare "remote" in a temporary directory.
+    fn remote() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let remote = dir.path().join("remote.git");
+        Repository::init_bare(&remote).unwrap();
+        (dir, remote)
+    }
+
+    fn clone(remote: &Path, name: &str) -> PathBuf {
+        let path = remote.with_file_name(name);
+        Repository::clone(remote.to_str().unwrap(), &path).unwrap();
+        path
+    }
+
+    fn groups(names: &[&str]) -> HashMap<String, GroupData> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), GroupData::new(name.to_string())))
+            .collect()
+    }
+
+    fn commit_groups(path: &Path, names: &[&str]) {
+        write_groups(path, FILE, &groups(names), &HashMap::new()).unwrap();
+        commit_file(path, FILE, &format!("Set {}", names.join(", "))).unwrap();
+    }
+
+    #[test]
+    fn commit_and_status() {
+        let (_dir, remote) = remote();
+        let a = clone(&remote, "a");
+        let fresh = status(&a).unwrap();
+        assert!(fresh.branch.is_some());
+        assert!(fresh.changes.is_empty());
+
+        write_groups(&a, FILE, &groups(&["x"]), &HashMap::new()).unwrap();
+        assert_eq!(status(&a).unwrap().changes, [(FILE.to_string(), "??")]);
+        commit_file(&a, FILE, "Add x").unwrap();
+        assert!(status(&a).unwrap().changes.is_empty());
+        assert_eq!(head_groups(&a, FILE).unwrap(), Some(groups(&["x"])));
+        assert!(commit_file(&a, FILE, "Again").is_err());
+
+        write_groups(&a, FILE, &groups(&["x", "y"]), &HashMap::new()).unwrap();
+        assert_eq!(status(&a).unwrap().changes, [(FILE.to_string(), "M")]);
+
+        create_branch(&a, "feature").unwrap();
+        let branched = status(&a).unwrap();
+        assert_eq!(branched.branch.as_deref(), Some("feature"));
+        // Uncommitted edits carry over to the new branch.
+        assert_eq!(branched.changes, [(FILE.to_string(), "M")]);
+    }
+
+    #[test]
+    fn push_then_pull_fast_forwards() {
+        let (_dir, remote) = remote();
+        let a = clone(&remote, "a");
+        commit_groups(&a, &["x"]);
+        push(&a, "origin", None).unwrap();
+        let pushed = status(&a).unwrap();
+        let branch = pushed.branch.unwrap();
+        assert_eq!(pushed.upstream, Some(format!("origin/{}", branch)));
+        assert_eq!((pushed.ahead, pushed.behind), (0, 0));
+
+        let b = clone(&remote, "b");
+        assert_eq!(head_groups(&b, FILE).unwrap(), Some(groups(&["x"])));
+        commit_groups(&b, &["x", "y"]);
+        push(&b, "origin", None).unwrap();
+
+        assert!(pull(&a, "origin", None)
+            .unwrap()
+            .starts_with("Fast-forwarded"));
+        assert_eq!(head_groups(&a, FILE).unwrap(), Some(groups(&["x", "y"])));
+        assert!(status(&a).unwrap().changes.is_empty());
+        assert_eq!(pull(&a, "origin", None).unwrap(), "Already up to date");
+    }
+
+    #[test]
+    fn diverged_pull_and_push_are_refused() {
+        let (_dir, remote) = remote();
+        let a = clone(&remote, "a");
+        commit_groups(&a, &["x"]);
+        push(&a, "origin", None).unwrap();
+        let b = clone(&remote, "b");
+        commit_groups(&b, &["x", "y"]);
+        push(&b, "origin", None).unwrap();
+
+        commit_groups(&a, &["x", "z"]);
+        let error = pull(&a, "origin", None).unwrap_err();
+        assert!(error.contains("diverged"), "{}", error);
+        // Neither the branch nor the file moved.
+        assert_eq!(head_groups(&a, FILE).unwrap(), Some(groups(&["x", "z"])));
+        assert!(status(&a).unwrap().changes.is_empty());
+        assert!(push(&a, "origin", None).is_err());
+    }
+
+    #[test]
+    fn token_is_only_for_github() {
+        assert!(is_github("https://github.com/owner/repo.git"));
+        assert!(is_github("https://user@GitHub.com:443/owner/repo"));
+        assert!(!is_github("https://github.com.evil.example/owner/repo"));
+        assert!(!is_github("https://gitlab.com/github.com/repo"));
+        assert!(!is_github("http://github.com/owner/repo"));
+        assert!(!is_github("git@github.com:owner/repo.git"));
+    }
+}
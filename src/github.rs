@@ -0,0 +1,268 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+pub const DEFAULT_LOGIN_URL: &str = "https://github.com";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub login: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Repo {
+    pub name: String,
+    pub owner: User,
+    pub default_branch: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Ref {
+    object: RefObject,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RefObject {
+    sha: String,
+}
+
+/// A file from the contents API. `content` is base64 with line breaks.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileContents {
+    pub sha: String,
+    #[serde(default)]
+    content: String,
+}
+
+impl FileContents {
+    pub fn text(&self) -> Result<String, String> {
+        let encoded: String = self.content.split_whitespace().collect();
+        let bytes = STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("Error decoding file: {}", e))?;
+        String::from_utf8(bytes).map_err(|e| format!("Error decoding file: {}", e))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub html_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// Minimal GitHub REST client. The API base URL is configurable so a local
+/// mock server can stand in for api.github.com.
+#[derive(Debug, Clone)]
+pub struct GithubClient {
+    base_url: String,
+    token: Option<String>,
+    http: Client,
+}
+
+impl GithubClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+            http: Client::builder()
+                .user_agent(concat!("group-json-manager/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// Sends `token` as a bearer token with every request.
+    pub fn with_token(mut self, token: Option<&str>) -> Self {
+        self.token = token.map(str::to_string);
+        self
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.request(Method::GET, path)
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path))
+            .header("Accept", "application/vnd.github+json");
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Sends a request and decodes the JSON body. A 404 is `Ok(None)`.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, String> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Error fetching: {}", e))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("GitHub returned {}: {}", status, body));
+        }
+        response
+            .json::<T>()
+            .await
+            .map(Some)
+            .map_err(|e| format!("Error deserializing: {}", e))
+    }
+
+    /// Published releases of a repository, newest first. `None` if the
+    /// repository doesn't exist.
+    pub async fn releases(&self, owner: &str, repo: &str) -> Result<Option<Vec<Release>>, String> {
+        let request = self.get(&format!("/repos/{}/{}/releases?per_page=100", owner, repo));
+        self.send(request).await
+    }
+
+    /// The account the token belongs to.
+    pub async fn user(&self) -> Result<Option<User>, String> {
+        self.send(self.get("/user")).await
+    }
+
+    /// Like `send`, for requests where a 404 is an error too.
+    async fn send_found<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, String> {
+        self.send(request)
+            .await?
+            .ok_or_else(|| "GitHub returned 404 Not Found".to_string())
+    }
+
+    pub async fn repo(&self, owner: &str, repo: &str) -> Result<Option<Repo>, String> {
+        self.send(self.get(&format!("/repos/{}/{}", owner, repo)))
+            .await
+    }
+
+    /// Forks `owner/repo` into the signed in account. GitHub creates forks
+    /// in the background, so the fork may not be usable straight away.
+    pub async fn create_fork(&self, owner: &str, repo: &str) -> Result<Repo, String> {
+        let request = self
+            .request(Method::POST, &format!("/repos/{}/{}/forks", owner, repo))
+            .json(&json!({}));
+        self.send_found(request).await
+    }
+
+    /// The commit a branch points at, `None` if there is no such branch.
+    pub async fn branch_sha(
+        &self,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+    ) -> Result<Option<String>, String> {
+        let request = self.get(&format!(
+            "/repos/{}/{}/git/ref/heads/{}",
+            owner, repo, branch
+        ));
+        Ok(self
+            .send::<Ref>(request)
+            .await?
+            .map(|reference| reference.object.sha))
+    }
+
+    pub async fn create_branch(
+        &self,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+        sha: &str,
+    ) -> Result<(), String> {
+        let request = self
+            .request(Method::POST, &format!("/repos/{}/{}/git/refs", owner, repo))
+            .json(&json!({ "ref": format!("refs/heads/{}", branch), "sha": sha }));
+        self.send_found::<Ref>(request).await.map(|_| ())
+    }
+
+    pub async fn file(
+        &self,
+        owner: &str,
+        repo: &str,
+        path: &str,
+        branch: &str,
+    ) -> Result<Option<FileContents>, String> {
+        let request = self.get(&format!(
+            "/repos/{}/{}/contents/{}?ref={}",
+            owner, repo, path, branch
+        ));
+        self.send(request).await
+    }
+
+    /// Commits `text` to `path` on `branch`. `sha` is the blob being
+    /// replaced, `None` when creating the file.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn put_file(
+        &self,
+        owner: &str,
+        repo: &str,
+        path: &str,
+        branch: &str,
+        message: &str,
+        text: &str,
+        sha: Option<&str>,
+    ) -> Result<(), String> {
+        let mut body = json!({
+            "message": message,
+            "content": STANDARD.encode(text),
+            "branch": branch,
+        });
+        if let Some(sha) = sha {
+            body["sha"] = json!(sha);
+        }
+        let request = self
+            .request(
+                Method::PUT,
+                &format!("/repos/{}/{}/contents/{}", owner, repo, path),
+            )
+            .json(&body);
+        self.send_found::<serde_json::Value>(request)
+            .await
+            .map(|_| ())
+    }
+
+    /// Opens a pull request into `owner/repo`. `head` is `user:branch` for
+    /// a branch on a fork.
+    pub async fn create_pull(
+        &self,
+        owner: &str,
+        repo: &str,
+        title: &str,
+        body: &str,
+        head: &str,
+        base: &str,
+    ) -> Result<PullRequest, String> {
+        let request = self
+            .request(Method::POST, &format!("/repos/{}/{}/pulls", owner, repo))
+            .json(&json!({ "title": title, "body": body, "head": head, "base": base }));
+        self.send_found(request).await
+    }
+}
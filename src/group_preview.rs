@@ -0,0 +1,121 @@
+use eframe::egui::{self, Color32, RichText, Stroke, Window};
+
+use crate::markdown;
+use crate::types::{GroupData, Project};
+use crate::widgets;
+
+const CARD_WIDTH: f32 = 300.0;
+/// Used where the palette has no valid colour.
+const FALLBACK: Color32 = Color32::from_gray(45);
+
+/// Black or white, whichever reads better on `background`.
+fn text_colour(background: Color32) -> Color32 {
+    let luma = 0.299 * background.r() as f32
+        + 0.587 * background.g() as f32
+        + 0.114 * background.b() as f32;
+    if luma > 150.0 {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+/// The selected group laid out roughly as its page on the Infinity site,
+/// redrawn every frame so it follows the form as it is edited.
+#[derive(Debug, Clone, Default)]
+pub struct PagePreview {
+    pub open: bool,
+}
+
+impl PagePreview {
+    pub fn ui(&mut self, ctx: &egui::Context, group: Option<&GroupData>) {
+        Window::new("Page preview")
+            .open(&mut self.open)
+            .default_width(2.0 * CARD_WIDTH + 40.0)
+            .default_height(600.0)
+            .vscroll(true)
+            .show(ctx, |ui| match group {
+                Some(group) => page(ui, group),
+                None => {
+                    ui.label("Select a group to preview its page");
+                }
+            });
+    }
+}
+
+fn page(ui: &mut egui::Ui, group: &GroupData) {
+    let primary = widgets::parse_colour(&group.palette.primary).unwrap_or(FALLBACK);
+    let secondary = widgets::parse_colour(&group.palette.secondary).unwrap_or(primary);
+
+    if group.hide == Some(true) {
+        ui.colored_label(Color32::YELLOW, "This group is hidden on the site");
+    }
+    egui::Frame::none()
+        .fill(primary)
+        .stroke(Stroke::new(3.0, secondary))
+        .inner_margin(12.0)
+        .rounding(4.0)
+        .show(ui, |ui| {
+            ui.set_min_width(ui.available_width());
+            ui.horizontal(|ui| {
+                widgets::image_preview(ui, &group.logo, 64.0);
+                ui.label(
+                    RichText::new(&group.name)
+                        .size(26.0)
+                        .strong()
+                        .color(text_colour(primary)),
+                );
+            });
+        });
+    ui.add_space(8.0);
+
+    ui.horizontal_wrapped(|ui| {
+        for project in &group.projects {
+            project_card(ui, project, secondary);
+        }
+    });
+    if group.projects.is_empty() {
+        ui.label(RichText::new("No projects").italics());
+    }
+
+    if !group.beta.background.trim().is_empty() {
+        ui.add_space(8.0);
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.set_min_width(ui.available_width());
+            ui.label(RichText::new("Beta").size(20.0).strong().color(secondary));
+            widgets::image_preview(ui, &group.beta.background, 2.0 * CARD_WIDTH);
+        });
+    }
+}
+
+fn project_card(ui: &mut egui::Ui, project: &Project, accent: Color32) {
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.set_width(CARD_WIDTH);
+        ui.vertical(|ui| {
+            widgets::image_preview(ui, &project.background, CARD_WIDTH);
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(&project.name).size(17.0).strong());
+                if !project.version.is_empty() {
+                    egui::Frame::none()
+                        .fill(accent)
+                        .rounding(8.0)
+                        .inner_margin(egui::Margin::symmetric(6.0, 1.0))
+                        .show(ui, |ui| {
+                            ui.label(
+                                RichText::new(project.version.to_string())
+                                    .small()
+                                    .color(text_colour(accent)),
+                            );
+                        });
+                }
+            });
+            if !project.date.is_empty() {
+                ui.small(project.date.to_string());
+            }
+            if let Some(variants) = project.variants.as_ref().filter(|v| !v.is_empty()) {
+                ui.small(variants.join(" · "));
+            }
+            markdown::preview(ui, &project.overview);
+        });
+    });
+}
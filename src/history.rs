@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use crate::types::GroupData;
+
+const LIMIT: usize = 100;
+
+/// Snapshot-based undo history. Edits are folded into a single step until
+/// `commit` is called, so a whole text field edit or a multi-field operation
+/// like a release undoes in one go.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: Vec<HashMap<String, GroupData>>,
+    redo: Vec<HashMap<String, GroupData>>,
+    last: HashMap<String, GroupData>,
+}
+
+impl History {
+    /// Records `current` as a new step if it differs from the last one.
+    /// The first data loaded is taken as the starting point rather than as a
+    /// step, so undo never empties the editor.
+    pub fn commit(&mut self, current: &HashMap<String, GroupData>) {
+        if self.last == *current {
+            return;
+        }
+        if !self.last.is_empty() {
+            self.undo.push(std::mem::take(&mut self.last));
+            if self.undo.len() > LIMIT {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+        }
+        self.last = current.clone();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self, current: &mut HashMap<String, GroupData>) {
+        self.commit(current);
+        if let Some(previous) = self.undo.pop() {
+            self.redo.push(std::mem::replace(current, previous));
+            self.last = current.clone();
+        }
+    }
+
+    pub fn redo(&mut self, current: &mut HashMap<String, GroupData>) {
+        self.commit(current);
+        if let Some(next) = self.redo.pop() {
+            self.undo.push(std::mem::replace(current, next));
+            self.last = current.clone();
+        }
+    }
+}
@@ -0,0 +1,700 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+
+use image::io::Reader as ImageReader;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+#[cfg(feature = "avif")]
+use crate::avif;
+use crate::types::GroupData;
+use crate::validate::Severity;
+
+/// How many URLs are requested at the same time.
+const CONCURRENT_REQUESTS: usize = 8;
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// How far an image's shape may be from a rule's aspect ratio, relative.
+const ASPECT_TOLERANCE: f64 = 0.02;
+
+/// What the checker needs from an HTTP response.
+#[derive(Debug, Clone, Default)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    /// Empty for HEAD requests.
+    pub body: Vec<u8>,
+}
+
+/// The HTTP layer the checker runs on. Implemented for `reqwest::Client`;
+/// anything else that can do HEAD and GET, like a canned fake, works too.
+pub trait Http: Clone + Send + Sync + 'static {
+    fn head(&self, url: &str) -> impl Future<Output = Result<Response, String>> + Send;
+    fn get(&self, url: &str) -> impl Future<Output = Result<Response, String>> + Send;
+}
+
+impl Http for reqwest::Client {
+    async fn head(&self, url: &str) -> Result<Response, String> {
+        let response = self
+            .head(url)
+            .send()
+            .await
+            .map_err(|e| format!("Error requesting {}: {}", url, e))?;
+        Ok(response_info(&response))
+    }
+
+    async fn get(&self, url: &str) -> Result<Response, String> {
+        let response = self
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("Error requesting {}: {}", url, e))?;
+        let info = response_info(&response);
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Error downloading {}: {}", url, e))?;
+        Ok(Response {
+            body: body.to_vec(),
+            ..info
+        })
+    }
+}
+
+fn response_info(response: &reqwest::Response) -> Response {
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    Response {
+        status: response.status().as_u16(),
+        content_type,
+        content_length: response.content_length(),
+        body: Vec::new(),
+    }
+}
+
+/// Client used for image checks, with a timeout so one slow host does not
+/// hold up the whole run.
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// Which image field of a group a URL came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldKind {
+    Logo,
+    BetaBackground,
+    Background,
+    PageBackground,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 4] = [
+        FieldKind::Logo,
+        FieldKind::BetaBackground,
+        FieldKind::Background,
+        FieldKind::PageBackground,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FieldKind::Logo => "logo",
+            FieldKind::BetaBackground => "beta background",
+            FieldKind::Background => "background",
+            FieldKind::PageBackground => "page background",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageField {
+    pub group: String,
+    /// `None` for group level fields.
+    pub project: Option<String>,
+    pub kind: FieldKind,
+    pub url: String,
+}
+
+impl fmt::Display for ImageField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.project {
+            Some(project) => write!(f, "{} / {}: {}", self.group, project, self.kind.label()),
+            None => write!(f, "{}: {}", self.group, self.kind.label()),
+        }
+    }
+}
+
+/// Every non-empty image URL in `groups`, sorted by group name.
+pub fn image_fields(groups: &HashMap<String, GroupData>) -> Vec<ImageField> {
+    let mut names: Vec<&String> = groups.keys().collect();
+    names.sort();
+    let mut fields = Vec::new();
+    for name in names {
+        let group = &groups[name];
+        let mut push = |project: Option<&str>, kind, url: &str| {
+            if !url.trim().is_empty() {
+                fields.push(ImageField {
+                    group: name.clone(),
+                    project: project.map(str::to_string),
+                    kind,
+                    url: url.trim().to_string(),
+                });
+            }
+        };
+        push(None, FieldKind::Logo, &group.logo);
+        push(None, FieldKind::BetaBackground, &group.beta.background);
+        for project in &group.projects {
+            push(
+                Some(&project.name),
+                FieldKind::Background,
+                &project.background,
+            );
+            if let Some(url) = &project.pageBackground {
+                push(Some(&project.name), FieldKind::PageBackground, url);
+            }
+        }
+    }
+    fields
+}
+
+/// What was found at one URL.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UrlInfo {
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    pub size: Option<u64>,
+    /// Format worked out from the bytes, which may not match the content type.
+    pub format: Option<String>,
+    pub dimensions: Option<(u32, u32)>,
+    pub problem: Option<(Severity, String)>,
+}
+
+impl UrlInfo {
+    fn problem(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            problem: Some((severity, message.into())),
+            ..Self::default()
+        }
+    }
+
+    /// Width to height, as a small ratio like 16:9 when there is one.
+    pub fn aspect_ratio(&self) -> Option<String> {
+        let (width, height) = self.dimensions.filter(|(w, h)| *w > 0 && *h > 0)?;
+        let divisor = gcd(width, height);
+        let (w, h) = (width / divisor, height / divisor);
+        Some(if w <= 32 && h <= 32 {
+            format!("{}:{}", w, h)
+        } else {
+            format!("{:.2}:1", f64::from(width) / f64::from(height))
+        })
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// What an image in one kind of field is expected to look like.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ImageRule {
+    /// Expected `width:height`, empty to allow any shape.
+    pub aspect_ratio: String,
+    /// Largest file size in KB, 0 for no limit.
+    pub max_kb: u64,
+    /// Smallest width in pixels, 0 for no limit.
+    pub min_width: u32,
+    /// Largest width in pixels, 0 for no limit. Imported images are scaled
+    /// down to it.
+    pub max_width: u32,
+}
+
+impl ImageRule {
+    fn new(aspect_ratio: &str, max_kb: u64, max_width: u32) -> Self {
+        Self {
+            aspect_ratio: aspect_ratio.to_string(),
+            max_kb,
+            min_width: 0,
+            max_width,
+        }
+    }
+
+    /// The aspect ratio as width divided by height, `None` if any is allowed.
+    pub fn aspect(&self) -> Result<Option<f64>, String> {
+        let text = self.aspect_ratio.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || format!("\"{}\" is not a width:height ratio", text);
+        let (width, height) = text.split_once(':').ok_or_else(invalid)?;
+        let width: f64 = width.trim().parse().map_err(|_| invalid())?;
+        let height: f64 = height.trim().parse().map_err(|_| invalid())?;
+        if width <= 0.0 || height <= 0.0 {
+            return Err(invalid());
+        }
+        Ok(Some(width / height))
+    }
+
+    /// Every way `info` breaks this rule. Rules that cannot be parsed are
+    /// skipped; the settings point them out.
+    pub fn violations(&self, info: &UrlInfo) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let (Ok(Some(expected)), Some((width, height))) = (self.aspect(), info.dimensions) {
+            let actual = f64::from(width) / f64::from(height.max(1));
+            if (actual - expected).abs() / expected > ASPECT_TOLERANCE {
+                violations.push(format!(
+                    "aspect ratio {} should be {}",
+                    info.aspect_ratio().unwrap_or_default(),
+                    self.aspect_ratio.trim()
+                ));
+            }
+        }
+        if let Some(size) = info.size.filter(|_| self.max_kb > 0) {
+            if size > self.max_kb * 1024 {
+                violations.push(format!(
+                    "{} KB is over the {} KB limit",
+                    size.div_ceil(1024),
+                    self.max_kb
+                ));
+            }
+        }
+        if let Some((width, _)) = info.dimensions.filter(|_| self.min_width > 0) {
+            if width < self.min_width {
+                violations.push(format!(
+                    "{} px wide, narrower than {} px",
+                    width, self.min_width
+                ));
+            }
+        }
+        if let Some((width, _)) = info.dimensions.filter(|_| self.max_width > 0) {
+            if width > self.max_width {
+                violations.push(format!(
+                    "{} px wide, wider than {} px",
+                    width, self.max_width
+                ));
+            }
+        }
+        violations
+    }
+}
+
+/// The rule for each kind of image field.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ImageRules {
+    pub logo: ImageRule,
+    pub beta_background: ImageRule,
+    pub background: ImageRule,
+    pub page_background: ImageRule,
+}
+
+impl Default for ImageRules {
+    fn default() -> Self {
+        Self {
+            logo: ImageRule::new("1:1", 200, 512),
+            beta_background: ImageRule::new("16:9", 500, 1920),
+            background: ImageRule::new("16:9", 500, 1920),
+            page_background: ImageRule::new("16:9", 500, 1920),
+        }
+    }
+}
+
+impl ImageRules {
+    pub fn get(&self, kind: FieldKind) -> &ImageRule {
+        match kind {
+            FieldKind::Logo => &self.logo,
+            FieldKind::BetaBackground => &self.beta_background,
+            FieldKind::Background => &self.background,
+            FieldKind::PageBackground => &self.page_background,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: FieldKind) -> &mut ImageRule {
+        match kind {
+            FieldKind::Logo => &mut self.logo,
+            FieldKind::BetaBackground => &mut self.beta_background,
+            FieldKind::Background => &mut self.background,
+            FieldKind::PageBackground => &mut self.page_background,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageCheck {
+    pub field: ImageField,
+    pub info: UrlInfo,
+    /// Ways the image breaks the rule for its field.
+    pub violations: Vec<String>,
+}
+
+impl ImageCheck {
+    pub fn is_broken(&self) -> bool {
+        matches!(self.info.problem, Some((Severity::Error, _)))
+    }
+
+    /// Everything wrong with the image, worst first.
+    pub fn problems(&self) -> Vec<(Severity, String)> {
+        let mut problems: Vec<(Severity, String)> = self.info.problem.iter().cloned().collect();
+        problems.extend(
+            self.violations
+                .iter()
+                .map(|violation| (Severity::Warning, violation.clone())),
+        );
+        problems
+    }
+}
+
+impl fmt::Display for ImageCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.field.url)?;
+        let info = &self.info;
+        if let Some(status) = info.status {
+            write!(f, " HTTP {}", status)?;
+        }
+        if let Some(content_type) = &info.content_type {
+            write!(f, " {}", content_type)?;
+        }
+        if let Some(size) = info.size {
+            write!(f, " {} bytes", size)?;
+        }
+        if let Some(format) = &info.format {
+            write!(f, " {}", format)?;
+        }
+        if let Some((width, height)) = info.dimensions {
+            write!(f, " {}x{}", width, height)?;
+        }
+        if let Some(aspect_ratio) = info.aspect_ratio() {
+            write!(f, " {}", aspect_ratio)?;
+        }
+        let problems: Vec<String> = self
+            .problems()
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect();
+        if !problems.is_empty() {
+            write!(f, " ({})", problems.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// What the last check found wrong with `url` in a `kind` field.
+pub fn problems_for(checks: &[ImageCheck], kind: FieldKind, url: &str) -> Vec<(Severity, String)> {
+    checks
+        .iter()
+        .find(|check| check.field.kind == kind && check.field.url == url.trim())
+        .map(ImageCheck::problems)
+        .unwrap_or_default()
+}
+
+/// Tries HEAD first so missing images are reported without downloading
+/// anything, then GETs the image to measure it. Servers that refuse HEAD,
+/// or fail it, are only asked with GET.
+async fn check_url<C: Http>(client: &C, url: &str) -> UrlInfo {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return UrlInfo::problem(Severity::Error, "not an http(s) URL");
+    }
+    match client.head(url).await {
+        Ok(head) if head.status >= 400 && head.status != 405 && head.status != 501 => {
+            return UrlInfo {
+                status: Some(head.status),
+                content_type: head.content_type,
+                size: head.content_length,
+                format: None,
+                dimensions: None,
+                problem: Some((Severity::Error, format!("HTTP {}", head.status))),
+            };
+        }
+        Ok(_) | Err(_) => {}
+    }
+    let response = match client.get(url).await {
+        Ok(response) => response,
+        Err(e) => return UrlInfo::problem(Severity::Error, e),
+    };
+
+    let mut info = UrlInfo {
+        status: Some(response.status),
+        content_type: response.content_type.clone(),
+        size: Some(response.body.len() as u64),
+        format: None,
+        dimensions: None,
+        problem: None,
+    };
+    if !(200..300).contains(&response.status) {
+        info.problem = Some((Severity::Error, format!("HTTP {}", response.status)));
+        return info;
+    }
+    if let Some(content_type) = &response.content_type {
+        if !content_type.starts_with("image/") && content_type != "application/octet-stream" {
+            info.problem = Some((Severity::Error, format!("served as {}", content_type)));
+            return info;
+        }
+    }
+    let reader = ImageReader::new(Cursor::new(&response.body))
+        .with_guessed_format()
+        .ok();
+    info.format = reader
+        .as_ref()
+        .and_then(ImageReader::format)
+        .map(|format| format!("{:?}", format));
+    let dimensions = reader.and_then(|reader| reader.into_dimensions().ok());
+    #[cfg(feature = "avif")]
+    let dimensions = dimensions.or_else(|| avif::dimensions(&response.body));
+    info.dimensions = dimensions;
+    if info.dimensions.is_none() {
+        info.problem = Some((
+            Severity::Warning,
+            "could not read the image dimensions".to_string(),
+        ));
+    }
+    info
+}
+
+/// Checks every image URL in `groups`, a few at a time, and measures each
+/// image against the rule for its field. A URL used by several fields is
+/// only requested once.
+pub async fn check_groups<C: Http>(
+    client: C,
+    groups: &HashMap<String, GroupData>,
+    rules: &ImageRules,
+) -> Vec<ImageCheck> {
+    let fields = image_fields(groups);
+    let semaphore = Arc::new(Semaphore::new(CONCURRENT_REQUESTS));
+    let mut jobs = JoinSet::new();
+    let mut urls: Vec<&String> = fields.iter().map(|field| &field.url).collect();
+    urls.sort();
+    urls.dedup();
+    for url in urls {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let url = url.clone();
+        jobs.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let info = check_url(&client, &url).await;
+            (url, info)
+        });
+    }
+
+    let mut results = HashMap::new();
+    while let Some(result) = jobs.join_next().await {
+        if let Ok((url, info)) = result {
+            results.insert(url, info);
+        }
+    }
+    fields
+        .into_iter()
+        .map(|field| {
+            let info = results
+                .get(&field.url)
+                .cloned()
+                .unwrap_or_else(|| UrlInfo::problem(Severity::Error, "the check did not finish"));
+            let violations = if !matches!(info.problem, Some((Severity::Error, _))) {
+                rules.get(field.kind).violations(&info)
+            } else {
+                Vec::new()
+            };
+            ImageCheck {
+                field,
+                info,
+                violations,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Project;
+    use image::{ImageFormat, RgbImage};
+    use std::sync::Mutex;
+
+    /// Canned HEAD and GET answers per URL; anything else is a 404.
+    #[derive(Clone, Default)]
+    struct FakeHttp {
+        heads: HashMap<String, Result<Response, String>>,
+        gets: HashMap<String, Response>,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeHttp {
+        fn image(mut self, url: &str, head: Result<u16, &str>, get: Response) -> Self {
+            let head = head
+                .map(|status| Response {
+                    status,
+                    ..Response::default()
+                })
+                .map_err(str::to_string);
+            self.heads.insert(url.to_string(), head);
+            self.gets.insert(url.to_string(), get);
+            self
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl Http for FakeHttp {
+        async fn head(&self, url: &str) -> Result<Response, String> {
+            self.requests.lock().unwrap().push(format!("HEAD {}", url));
+            self.heads.get(url).cloned().unwrap_or(Ok(Response {
+                status: 404,
+                ..Response::default()
+            }))
+        }
+
+        async fn get(&self, url: &str) -> Result<Response, String> {
+            self.requests.lock().unwrap().push(format!("GET {}", url));
+            Ok(self.gets.get(url).cloned().unwrap_or(Response {
+                status: 404,
+                ..Response::default()
+            }))
+        }
+    }
+
+    fn png(width: u32, height: u32) -> Response {
+        let mut body = Vec::new();
+        RgbImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut body), ImageFormat::Png)
+            .unwrap();
+        Response {
+            status: 200,
+            content_type: Some("image/png".to_string()),
+            content_length: Some(body.len() as u64),
+            body,
+        }
+    }
+
+    async fn check(http: &FakeHttp, backgrounds: &[&str], rules: &ImageRules) -> Vec<ImageCheck> {
+        let mut group = GroupData::new("Group".to_string());
+        group.projects = backgrounds
+            .iter()
+            .map(|url| {
+                let mut project = Project::new(url.to_string());
+                project.background = url.to_string();
+                project
+            })
+            .collect();
+        let groups = HashMap::from([("group".to_string(), group)]);
+        check_groups(http.clone(), &groups, rules).await
+    }
+
+    #[tokio::test]
+    async fn head_refused_or_failed_falls_back_to_get() {
+        let http = FakeHttp::default()
+            .image("https://img/refused.png", Ok(405), png(160, 90))
+            .image(
+                "https://img/failed.png",
+                Err("connection reset"),
+                png(160, 90),
+            );
+        let checks = check(
+            &http,
+            &["https://img/refused.png", "https://img/failed.png"],
+            &ImageRules::default(),
+        )
+        .await;
+        for check in &checks {
+            assert_eq!(check.info.dimensions, Some((160, 90)));
+            assert!(check.problems().is_empty(), "{}", check);
+        }
+        let mut requests = http.requests();
+        requests.sort();
+        assert_eq!(
+            requests,
+            [
+                "GET https://img/failed.png",
+                "GET https://img/refused.png",
+                "HEAD https://img/failed.png",
+                "HEAD https://img/refused.png",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_image_is_not_downloaded() {
+        let http = FakeHttp::default();
+        let checks = check(&http, &["https://img/missing.png"], &ImageRules::default()).await;
+        assert!(checks[0].is_broken());
+        assert_eq!(
+            checks[0].problems(),
+            [(Severity::Error, "HTTP 404".to_string())]
+        );
+        assert_eq!(http.requests(), ["HEAD https://img/missing.png"]);
+    }
+
+    #[tokio::test]
+    async fn page_served_instead_of_image() {
+        let page = Response {
+            status: 200,
+            content_type: Some("text/html; charset=utf-8".to_string()),
+            content_length: Some(6),
+            body: b"<html>".to_vec(),
+        };
+        let http = FakeHttp::default().image("https://img/page.png", Ok(200), page);
+        let checks = check(&http, &["https://img/page.png"], &ImageRules::default()).await;
+        assert_eq!(
+            checks[0].problems(),
+            [(
+                Severity::Error,
+                "served as text/html; charset=utf-8".to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_and_misshapen_images() {
+        let mut big = png(160, 90);
+        big.body.resize(600 * 1024, 0);
+        let http = FakeHttp::default()
+            .image("https://img/big.png", Ok(200), big)
+            .image("https://img/square.png", Ok(200), png(100, 100))
+            .image("https://img/wide.png", Ok(200), png(320, 180));
+        let mut rules = ImageRules::default();
+        rules.background.max_width = 200;
+        let checks = check(
+            &http,
+            &[
+                "https://img/big.png",
+                "https://img/square.png",
+                "https://img/wide.png",
+            ],
+            &rules,
+        )
+        .await;
+        let problems: Vec<Vec<(Severity, String)>> =
+            checks.iter().map(ImageCheck::problems).collect();
+        assert_eq!(
+            problems,
+            [
+                vec![(
+                    Severity::Warning,
+                    "600 KB is over the 500 KB limit".to_string()
+                )],
+                vec![(
+                    Severity::Warning,
+                    "aspect ratio 1:1 should be 16:9".to_string()
+                )],
+                vec![(
+                    Severity::Warning,
+                    "320 px wide, wider than 200 px".to_string()
+                )],
+            ]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, Color32, Rect, Stroke};
+use image::imageops::FilterType;
+
+use crate::image_check::FieldKind;
+use crate::image_loader;
+use crate::settings::Settings;
+use crate::task::{self, SharedTask, Task};
+
+/// The field an imported image is for.
+pub struct Target<'a> {
+    pub group: &'a str,
+    /// `None` for group level fields.
+    pub project: Option<&'a str>,
+    pub kind: FieldKind,
+}
+
+/// Lower case with runs of anything but letters and digits turned into a
+/// single dash, so names can be used in paths and URLs.
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Path inside the assets checkout for `target`, from a template using
+/// `{group}`, `{project}` and `{field}`. Segments left empty, like
+/// `{project}` for a group logo, are dropped.
+pub fn asset_path(template: &str, target: &Target) -> String {
+    let path = template
+        .replace("{group}", &slug(target.group))
+        .replace("{project}", &target.project.map(slug).unwrap_or_default())
+        .replace("{field}", &slug(target.kind.label()));
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Scales the image down to `max_width` (0 for no limit) and encodes it as
+/// lossy WebP at `quality` (0-100).
+pub fn convert(bytes: &[u8], max_width: u32, quality: f32) -> Result<Vec<u8>, String> {
+    let mut image = image_loader::decode(bytes)?;
+    if max_width > 0 && image.width() > max_width {
+        image = image.resize(max_width, u32::MAX, FilterType::Lanczos3);
+    }
+    // The encoder only takes 8-bit RGB or RGBA.
+    let image = if image.color().has_alpha() {
+        image::DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        image::DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+    let encoder = webp::Encoder::from_image(&image)
+        .map_err(|e| format!("Error encoding image as WebP: {}", e))?;
+    Ok(encoder.encode(quality.clamp(0.0, 100.0)).to_vec())
+}
+
+/// An import worked out from the settings, owned so the slow part can run
+/// away from the UI thread.
+struct Import {
+    source: PathBuf,
+    max_width: u32,
+    quality: f32,
+    path: PathBuf,
+    url: String,
+}
+
+impl Import {
+    fn new(source: &Path, settings: &Settings, target: &Target) -> Result<Self, String> {
+        if settings.assets_checkout.trim().is_empty() || settings.assets_url.trim().is_empty() {
+            return Err(
+                "Set the assets checkout and its URL in the settings to import images".into(),
+            );
+        }
+        let relative = asset_path(&settings.assets_path, target);
+        Ok(Self {
+            source: source.to_path_buf(),
+            max_width: settings.image_rules.get(target.kind).max_width,
+            quality: settings.webp_quality,
+            path: Path::new(settings.assets_checkout.trim()).join(&relative),
+            url: format!(
+                "{}/{}",
+                settings.assets_url.trim().trim_end_matches('/'),
+                relative
+            ),
+        })
+    }
+
+    fn run(self) -> Result<(PathBuf, String), String> {
+        let bytes = std::fs::read(&self.source)
+            .map_err(|e| format!("Error reading {}: {}", self.source.display(), e))?;
+        let webp = convert(&bytes, self.max_width, self.quality)?;
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Error creating {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&self.path, webp)
+            .map_err(|e| format!("Error writing {}: {}", self.path.display(), e))?;
+        Ok((self.path, self.url))
+    }
+}
+
+/// Converts the image at `source` for `target`, writes it into the assets
+/// checkout and returns the path written and the URL it will be served from.
+pub fn import(
+    source: &Path,
+    settings: &Settings,
+    target: &Target,
+) -> Result<(PathBuf, String), String> {
+    Import::new(source, settings, target)?.run()
+}
+
+/// The last file dropped on a field and its import.
+#[derive(Clone)]
+struct Dropped {
+    source: PathBuf,
+    import: SharedTask<Result<(PathBuf, String), String>>,
+    /// Whether the field has been pointed at the imported image yet.
+    applied: bool,
+}
+
+/// Lets image files be dropped on `rect`. A dropped file is imported for
+/// `target` in the background and `url` is pointed at the result once it
+/// is done. What happened is shown under the field until the next drop.
+pub fn drop_target(
+    ui: &mut egui::Ui,
+    rect: Rect,
+    settings: &Settings,
+    target: &Target,
+    url: &mut String,
+) {
+    let id = ui
+        .id()
+        .with(("image_import", target.group, target.project, target.kind));
+    let (hovering, dropped) = ui.input(|i| {
+        (
+            !i.raw.hovered_files.is_empty(),
+            i.raw
+                .dropped_files
+                .first()
+                .and_then(|file| file.path.clone()),
+        )
+    });
+    let over = ui.rect_contains_pointer(rect);
+    if hovering && over {
+        ui.painter()
+            .rect_stroke(rect, 2.0, Stroke::new(2.0, Color32::LIGHT_BLUE));
+    }
+    if let Some(source) = dropped.filter(|_| over) {
+        let import = SharedTask::default();
+        match Import::new(&source, settings, target) {
+            Ok(job) => task::spawn(ui.ctx(), &import, async move { job.run() }),
+            Err(e) => *import.lock().unwrap() = Task::Done(Err(e)),
+        }
+        let dropped = Dropped {
+            source,
+            import,
+            applied: false,
+        };
+        ui.data_mut(|d| d.insert_temp(id, dropped));
+    }
+
+    let Some(mut dropped) = ui.data(|d| d.get_temp::<Dropped>(id)) else {
+        return;
+    };
+    let state = dropped.import.lock().unwrap().clone();
+    match state {
+        Task::Idle => {}
+        Task::Running => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Converting {}", dropped.source.display()));
+            });
+        }
+        Task::Done(Ok((path, new_url))) => {
+            if !dropped.applied {
+                *url = new_url;
+                dropped.applied = true;
+                ui.data_mut(|d| d.insert_temp(id, dropped.clone()));
+            }
+            ui.label(format!(
+                "Imported {} as {}",
+                dropped.source.display(),
+                path.display()
+            ));
+        }
+        Task::Done(Err(e)) => {
+            ui.colored_label(Color32::LIGHT_RED, e);
+        }
+    }
+}
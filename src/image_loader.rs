@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use std::mem::size_of;
+use std::sync::{Arc, Mutex};
+
+use eframe::egui::load::{BytesPoll, ImageLoadResult, ImageLoader, ImagePoll, LoadError, SizeHint};
+use eframe::egui::{self, Color32, ColorImage};
+use image::DynamicImage;
+
+#[cfg(feature = "avif")]
+use crate::avif;
+use crate::thumbnails::{self, Fetched, ThumbnailCache};
+
+type Entry = Result<Arc<ColorImage>, String>;
+
+#[derive(Clone)]
+enum State {
+    Decoding,
+    Done(Entry),
+}
+
+/// Decodes previews with our own `image` dependency. The loader that comes
+/// with egui_extras is built against an older `image` with only PNG enabled,
+/// so WebP and AVIF assets never showed. AVIF goes through `avif`. Decoding happens on a thread so a
+/// large background does not stall the editor.
+///
+/// Remote images go through the thumbnail cache: a cached thumbnail is
+/// shown straight away and checked against the server once per run.
+#[derive(Clone)]
+pub struct ImageCrateLoader {
+    cache: Arc<Mutex<HashMap<String, State>>>,
+    thumbnails: Arc<ThumbnailCache>,
+    /// URLs already checked against the server this run. Kept when images
+    /// are forgotten so a refreshed thumbnail is not fetched twice.
+    revalidated: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ImageCrateLoader {
+    pub const ID: &'static str = egui::generate_loader_id!(ImageCrateLoader);
+
+    pub fn new(thumbnails: Arc<ThumbnailCache>) -> Self {
+        Self {
+            cache: Arc::default(),
+            thumbnails,
+            revalidated: Arc::default(),
+        }
+    }
+
+    fn finish(&self, uri: String, entry: Entry) {
+        self.cache.lock().unwrap().insert(uri, State::Done(entry));
+    }
+
+    /// Shows the cached thumbnail of `uri` if there is one, then asks the
+    /// server whether it is still current. Runs on its own thread.
+    fn load_remote(&self, ctx: &egui::Context, uri: String) {
+        let cached = self.thumbnails.get(&uri);
+        let shown = cached.is_some();
+        let etag = cached.as_ref().and_then(|(etag, _)| etag.clone());
+        if let Some((_, image)) = cached {
+            self.finish(uri.clone(), Ok(color_image(&image)));
+            ctx.request_repaint();
+        }
+        let first = self.revalidated.lock().unwrap().insert(uri.clone());
+        if shown && !first {
+            return;
+        }
+
+        match thumbnails::fetch(&uri, etag.as_deref()) {
+            Ok(Fetched::NotModified) => {}
+            Ok(Fetched::Fresh { etag, bytes }) => {
+                let entry = decode(&bytes).map(|image| {
+                    match self.thumbnails.put(&uri, etag, &image) {
+                        Ok(thumbnail) => color_image(&thumbnail),
+                        // Not being able to cache it is no reason not to
+                        // show it.
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            color_image(&image)
+                        }
+                    }
+                });
+                if shown && entry.is_ok() {
+                    // Drops the old texture so the new image is shown.
+                    ctx.forget_image(&uri);
+                }
+                self.finish(uri, entry);
+            }
+            // A cached thumbnail is better than nothing when offline.
+            Err(_) if shown => {}
+            Err(e) => self.finish(uri, Err(e)),
+        }
+        ctx.request_repaint();
+    }
+}
+
+/// Registers the loader. It has to be installed after egui_extras' loaders,
+/// because egui asks the most recently added image loader first.
+pub fn install(ctx: &egui::Context, thumbnails: Arc<ThumbnailCache>) {
+    if !ctx.is_loader_installed(ImageCrateLoader::ID) {
+        ctx.add_image_loader(Arc::new(ImageCrateLoader::new(thumbnails)));
+    }
+}
+
+/// Decodes an image in any format the editor reads, AVIF included.
+pub fn decode(bytes: &[u8]) -> Result<DynamicImage, String> {
+    let format = image::guess_format(bytes).map_err(|e| e.to_string())?;
+    #[cfg(feature = "avif")]
+    if format == image::ImageFormat::Avif {
+        return avif::decode(bytes);
+    }
+    image::load_from_memory_with_format(bytes, format).map_err(|e| match e {
+        image::ImageError::Unsupported(_) => {
+            format!("{:?} images cannot be decoded by this build", format)
+        }
+        e => format!("Error decoding {:?} image: {}", format, e),
+    })
+}
+
+fn color_image(image: &DynamicImage) -> Arc<ColorImage> {
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Arc::new(ColorImage::from_rgba_unmultiplied(
+        size,
+        rgba.as_flat_samples().as_slice(),
+    ))
+}
+
+impl ImageLoader for ImageCrateLoader {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn load(&self, ctx: &egui::Context, uri: &str, _: SizeHint) -> ImageLoadResult {
+        if let Some(state) = self.cache.lock().unwrap().get(uri).cloned() {
+            return match state {
+                State::Decoding => Ok(ImagePoll::Pending { size: None }),
+                State::Done(Ok(image)) => Ok(ImagePoll::Ready { image }),
+                State::Done(Err(e)) => Err(LoadError::Loading(e)),
+            };
+        }
+        if uri.ends_with(".svg") {
+            return Err(LoadError::NotSupported);
+        }
+        if uri.starts_with("http://") || uri.starts_with("https://") {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(uri.to_string(), State::Decoding);
+            let loader = self.clone();
+            let ctx = ctx.clone();
+            let uri = uri.to_string();
+            std::thread::spawn(move || loader.load_remote(&ctx, uri));
+            return Ok(ImagePoll::Pending { size: None });
+        }
+        match ctx.try_load_bytes(uri) {
+            Ok(BytesPoll::Ready { bytes, mime, .. }) => {
+                // SVGs and anything that is not a raster image are left to
+                // the other loaders.
+                if mime.as_deref().is_some_and(|mime| mime.contains("svg"))
+                    || image::guess_format(&bytes).is_err()
+                {
+                    return Err(LoadError::NotSupported);
+                }
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .insert(uri.to_string(), State::Decoding);
+                let cache = self.cache.clone();
+                let ctx = ctx.clone();
+                let uri = uri.to_string();
+                std::thread::spawn(move || {
+                    let entry = decode(&bytes).map(|image| color_image(&image));
+                    cache.lock().unwrap().insert(uri, State::Done(entry));
+                    ctx.request_repaint();
+                });
+                Ok(ImagePoll::Pending { size: None })
+            }
+            Ok(BytesPoll::Pending { size }) => Ok(ImagePoll::Pending { size }),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn forget(&self, uri: &str) {
+        self.cache.lock().unwrap().remove(uri);
+    }
+
+    fn forget_all(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn byte_size(&self) -> usize {
+        self.cache
+            .lock()
+            .unwrap()
+            .values()
+            .map(|state| match state {
+                State::Done(Ok(image)) => image.pixels.len() * size_of::<Color32>(),
+                State::Done(Err(e)) => e.len(),
+                State::Decoding => 0,
+            })
+            .sum()
+    }
+}
@@ -1,15 +1,75 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use eframe::egui::{self, Color32, ComboBox, Id, Image, Label, Pos2, TextEdit, Vec2, Window};
+use eframe::egui::{
+    self, Color32, ComboBox, Id, Key, KeyboardShortcut, Modifiers, TextEdit, Vec2, Window,
+};
 use std::collections::HashMap;
-use std::io::Write;
-use types::{GroupData, Project};
+use types::{GroupData, Package, Project};
+mod auth;
+#[cfg(feature = "avif")]
+mod avif;
+mod bulk_edit;
+mod changelog;
+mod cli;
+mod date;
+mod diff;
+mod gallery;
+mod git;
+mod github;
+mod group_preview;
+mod history;
+mod image_check;
+mod image_import;
+mod image_loader;
+mod markdown;
+#[cfg(test)]
+mod mock_server;
+mod raw_editor;
+mod release;
+mod release_check;
+mod release_sync;
+mod replace;
+mod schema;
+mod search;
+mod settings;
+mod submit;
+mod task;
+mod thumbnails;
 mod types;
+mod validate;
+mod version;
+mod widgets;
+mod workspace;
+use auth::AuthPanel;
+use bulk_edit::BulkEditWindow;
+use changelog::Changelog;
 use egui::ViewportCommand;
-use std::fs::File;
-use std::sync::{Arc, Mutex};
+use gallery::Gallery;
+use git::CheckoutPanel;
+use group_preview::PagePreview;
+use image_check::{FieldKind, ImageCheck};
+use release::ReleaseWizard;
+use release_check::Finding;
+use release_sync::SyncWizard;
+use replace::ReplaceWindow;
+use search::{Reveal, SearchPanel};
+use settings::Settings;
+use std::path::PathBuf;
+use std::sync::Arc;
+use submit::SubmitWindow;
+use task::Task;
+use thumbnails::ThumbnailCache;
+use validate::{LoadReport, Severity};
+use widgets::Confirm;
+use workspace::{Document, Item, Repair, Source};
 
 fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        cli::attach_console();
+        std::process::exit(cli::run(&args));
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 800.0])
@@ -22,29 +82,70 @@ fn main() -> Result<(), eframe::Error> {
         "Group Manager",
         options,
         Box::new(|cc| {
+            let app = JsonApp::default();
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Box::<JsonApp>::default()
+            image_loader::install(&cc.egui_ctx, app.thumbnails.clone());
+            Box::new(app)
         }),
     )
 }
 
 #[derive(Debug, Clone)]
 struct JsonApp {
-    group_data: Arc<Mutex<HashMap<String, GroupData>>>,
-    selected_group: String,
-    selected_project: Option<usize>,
-    new_group_name: String,
-    new_project_name: String,
+    documents: Vec<Document>,
+    active: usize,
+    /// A tab with unsaved changes waiting for the user to confirm closing it.
+    closing: Option<usize>,
+    fetch_url: String,
+    open_path: String,
+    transfer_target: usize,
+    transfer_group: String,
+    transfer_error: Option<String>,
+    view: View,
+    settings: Settings,
+    auth: AuthPanel,
+    checkout: CheckoutPanel,
+    gallery: Gallery,
+    search: SearchPanel,
+    replace: ReplaceWindow,
+    page_preview: PagePreview,
+    /// A field picked in the search to scroll to.
+    reveal: Option<Reveal>,
+    /// Shared with the image loader, which fills it.
+    thumbnails: Arc<ThumbnailCache>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Form,
+    Gallery,
+    Raw,
 }
 
 impl Default for JsonApp {
     fn default() -> Self {
+        let settings = Settings::load();
+        let thumbnails =
+            ThumbnailCache::new(thumbnails::CACHE_DIR, settings.thumbnail_cache_bytes());
         Self {
-            group_data: Arc::new(Mutex::new(HashMap::new())),
-            selected_group: String::new(),
-            selected_project: None,
-            new_group_name: String::new(),
-            new_project_name: String::new(),
+            documents: vec![Document::default()],
+            active: 0,
+            closing: None,
+            fetch_url: GROUPS_URL.to_string(),
+            open_path: "groups.json".to_string(),
+            transfer_target: 0,
+            transfer_group: String::new(),
+            transfer_error: None,
+            view: View::Form,
+            settings,
+            auth: AuthPanel::default(),
+            checkout: CheckoutPanel::default(),
+            gallery: Gallery::default(),
+            search: SearchPanel::default(),
+            replace: ReplaceWindow::default(),
+            page_preview: PagePreview::default(),
+            reveal: None,
+            thumbnails: Arc::new(thumbnails),
         }
     }
 }
@@ -64,266 +165,1181 @@ impl eframe::App for JsonApp {
                 .animated(true)
                 .enable_scrolling(true)
                 .show(ui, |ui| {
-                    let group_data = self.group_data.clone();
-
                     ui.heading("Infinity Groups Manager");
 
-                    if ui.button("Fetch group data from repo").clicked() {
-                        let callback = move |result: Result<HashMap<String, GroupData>, String>| {
-                            match result {
-                                Ok(data) => {
-                                    let mut data_locked = group_data.lock().unwrap();
-                                    *data_locked = data;
-                                    drop(data_locked);
-                                }
-                                Err(e) => {
-                                    eprintln!("error fetching: {}", e)
-                                }
-                            }
-                        };
+                    ui.horizontal(|ui| {
+                        if ui.button("Fetch group data from repo").clicked() {
+                            let index = self.open(Source::Url(self.fetch_url.clone()));
+                            let store =
+                                self.documents[index].loader(self.settings.date_format(), true);
+                            let link = self.fetch_url.clone();
+                            let date_format = self.settings.date_format().to_string();
 
-                        std::thread::spawn(move || {
-                            let runtime = tokio::runtime::Runtime::new().unwrap();
-                            runtime.block_on(async move {
-                                let result = fetch_data().await;
-                                callback(result);
-                            })
-                        });
+                            std::thread::spawn(move || {
+                                let runtime = tokio::runtime::Runtime::new().unwrap();
+                                runtime.block_on(async move {
+                                    let result = fetch_data(&link, &date_format).await;
+                                    store(result);
+                                })
+                            });
+                        }
+                        ui.text_edit_singleline(&mut self.fetch_url);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Load local file").clicked() {
+                            let path = PathBuf::from(self.open_path.trim());
+                            let result = read_groups_file(&path, self.settings.date_format());
+                            let index = self.open(Source::File(path));
+                            self.documents[index].loader(self.settings.date_format(), false)(
+                                result,
+                            );
+                        }
+                        ui.text_edit_singleline(&mut self.open_path);
+                    });
+                    self.settings_ui(ui);
+                    self.auth.ui(ui, &self.settings);
+                    let action = {
+                        let doc = &self.documents[self.active];
+                        let groups = doc.group_data.lock().unwrap();
+                        let broken = doc.load_report.lock().unwrap().broken.clone();
+                        self.checkout
+                            .ui(ui, &self.settings, &groups, &broken, self.auth.token())
+                    };
+                    if let git::Action::Load(path) = action {
+                        let result = read_groups_file(&path, self.settings.date_format());
+                        let index = self.open(Source::File(path));
+                        self.documents[index].loader(self.settings.date_format(), true)(result);
                     }
-                    let mut locked_data = self.group_data.lock().unwrap();
-                    if !locked_data.is_empty() {
-                        ui.horizontal(|ui| {
-                            ui.label("New group name:");
-                            ui.text_edit_singleline(&mut self.new_group_name);
-                            if ui.button("Add Group").clicked() {
-                                locked_data.insert(
-                                    self.new_group_name.clone(),
-                                    GroupData::new(self.new_group_name.clone()),
-                                );
-                                self.new_group_name.clear();
-                            }
-                        });
+                    self.tabs_ui(ui);
+                    self.transfer_ui(ui);
 
-                        let mut selected_item = self.selected_group.clone();
-                        ui.horizontal(|ui: &mut egui::Ui| {
-                            if ui.button("Output group.json file").clicked() {
-                                write_locked_data(locked_data.clone())
-                            }
+                    // The shown document is taken out of the list while it is
+                    // drawn so it can be borrowed alongside the rest of the app.
+                    let mut doc = std::mem::take(&mut self.documents[self.active]);
+                    self.document_ui(ui, &mut doc);
+                    self.documents[self.active] = doc;
+                });
+        });
+    }
+}
 
-                            ComboBox::from_id_source(Id::new("Groups"))
-                                .selected_text(format!("Select Group"))
-                                .show_ui(ui, |ui| {
-                                    for (name, _) in locked_data.iter() {
-                                        ui.selectable_value(&mut selected_item, name.clone(), name);
-                                    }
-                                });
-                        });
+impl JsonApp {
+    fn document_ui(&mut self, ui: &mut egui::Ui, doc: &mut Document) {
+        self.load_problems_ui(ui, doc);
+        let shared_data = doc.group_data.clone();
+        let mut locked_data = shared_data.lock().unwrap();
+        ui.horizontal(|ui| {
+            ui.label("New group name:");
+            ui.text_edit_singleline(&mut doc.new_group_name);
+            if ui.button("Add Group").clicked() {
+                locked_data.insert(
+                    doc.new_group_name.clone(),
+                    GroupData::new(doc.new_group_name.clone()),
+                );
+                doc.new_group_name.clear();
+            }
+        });
+
+        if let Some(hit) = self.search.ui(ui.ctx(), &locked_data) {
+            doc.selected_group = hit.group;
+            doc.selected_project = hit.project.map(|(index, _)| index);
+            self.view = View::Form;
+            self.reveal = Some(Reveal::new(hit.field));
+        }
+
+        let mut selected_item = doc.selected_group.clone();
+        ui.horizontal(|ui: &mut egui::Ui| {
+            let path = doc.source.save_path();
+            if ui.button(format!("Save {}", path.display())).clicked() {
+                if let Err(e) = doc.save_groups(&locked_data) {
+                    eprintln!("{}", e);
+                }
+            }
+            if ui.button("Submit changes").clicked() {
+                doc.submit = Some(SubmitWindow::new(
+                    &doc.upstream.lock().unwrap(),
+                    doc.upstream_unreadable.lock().unwrap().clone(),
+                    &locked_data,
+                ));
+            }
+            if ui.button("Output groups.schema.json file").clicked() {
+                if let Err(e) =
+                    schema::write_schema(schema::SCHEMA_FILE, self.settings.date_format())
+                {
+                    eprintln!("{}", e);
+                }
+            }
+            if ui
+                .add_enabled(doc.history.can_undo(), egui::Button::new("Undo"))
+                .clicked()
+            {
+                doc.history.undo(&mut locked_data);
+            }
+            if ui
+                .add_enabled(doc.history.can_redo(), egui::Button::new("Redo"))
+                .clicked()
+            {
+                doc.history.redo(&mut locked_data);
+            }
+            if ui.button("Find and replace").clicked() {
+                self.replace.show();
+            }
+            if ui.button("Bulk edit").clicked() {
+                let project = locked_data
+                    .get(&doc.selected_group)
+                    .zip(doc.selected_project)
+                    .and_then(|(group, index)| group.projects.get(index));
+                doc.bulk_edit = Some(BulkEditWindow::new(
+                    &doc.selected_group,
+                    project.map(|project| project.name.as_str()),
+                ));
+            }
+
+            let selected_text = if locked_data.contains_key(&selected_item) {
+                selected_item.clone()
+            } else {
+                "Select Group".to_string()
+            };
+            ComboBox::from_id_source(Id::new("Groups"))
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    let mut names: Vec<&String> = locked_data.keys().collect();
+                    names.sort_by_key(|name| name.to_lowercase());
+                    for name in names {
+                        ui.selectable_value(&mut selected_item, name.clone(), name);
+                    }
+                });
+        });
+
+        if doc.selected_group != selected_item {
+            doc.selected_project = None;
+        }
+
+        doc.selected_group = selected_item;
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.view, View::Form, "Form");
+            ui.selectable_value(&mut self.view, View::Gallery, "Gallery");
+            ui.selectable_value(&mut self.view, View::Raw, "Raw JSON");
+            ui.separator();
+            ui.checkbox(&mut self.page_preview.open, "Page preview");
+        });
+
+        let image_checks = match &*doc.image_checks.lock().unwrap() {
+            Task::Done(checks) => checks.clone(),
+            _ => Vec::new(),
+        };
+        if self.view == View::Raw {
+            doc.raw_editor.ui(
+                ui,
+                &mut locked_data,
+                &doc.selected_group,
+                self.settings.date_format(),
+            );
+        } else if self.view == View::Gallery {
+            if let Some(name) = self.gallery.ui(ui, &locked_data, &image_checks) {
+                doc.selected_group = name;
+                doc.selected_project = None;
+                self.view = View::Form;
+            }
+        } else if let Some(data) = locked_data.get_mut(&doc.selected_group) {
+            let heading = ui.heading(format!("Group: {}", data.name));
+            search::reveal(&mut self.reveal, ui, heading.rect, search::Field::GroupName);
+            ui.separator();
+            ui.heading("Projects");
 
-                        if self.selected_group != selected_item {
-                            self.selected_group = selected_item.clone();
-                            self.selected_project = None;
+            ui.label("New Project Name:");
+            ui.text_edit_singleline(&mut doc.new_project_name);
+            if ui.button("Add Project").clicked() {
+                data.projects
+                    .push(Project::new(doc.new_project_name.clone()));
+                doc.new_project_name.clear();
+            }
+
+            let mut selected_project = doc.selected_project.unwrap_or_default();
+            ComboBox::from_id_source(Id::new("Projects"))
+                .selected_text("Select Project")
+                .show_ui(ui, |ui| {
+                    for (index, project) in data.projects.iter().enumerate() {
+                        ui.selectable_value(&mut selected_project, index, project.name.clone());
+                    }
+                });
+            doc.selected_project = Some(selected_project);
+
+            if let Some(index) = doc.selected_project {
+                if !data.projects.is_empty() && index < data.projects.len() {
+                    ui.separator();
+                    let row = ui.horizontal(|ui| {
+                        ui.heading(format!("Project: {}", data.projects[index].name));
+                        if ui.button("Release project").clicked() {
+                            doc.release = Some(ReleaseWizard::new(
+                                &doc.selected_group,
+                                index,
+                                &data.projects[index],
+                                self.settings.date_format(),
+                            ));
+                        }
+                        let has_package = data.projects[index].package.is_some();
+                        if ui
+                            .add_enabled(has_package, egui::Button::new("Sync from latest release"))
+                            .clicked()
+                        {
+                            doc.sync = Some(SyncWizard::new(
+                                ui.ctx(),
+                                self.github_client(),
+                                &doc.selected_group,
+                                index,
+                                &data.projects[index],
+                            ));
                         }
+                    });
+                    search::reveal(
+                        &mut self.reveal,
+                        ui,
+                        row.response.rect,
+                        search::Field::ProjectName,
+                    );
 
-                        self.selected_group = selected_item;
+                    let upstream = doc.upstream.lock().unwrap();
+                    let upstream_project = upstream.get(&doc.selected_group).and_then(|group| {
+                        group
+                            .projects
+                            .iter()
+                            .find(|p| p.name == data.projects[index].name)
+                    });
+                    project_ui(
+                        ui,
+                        &data.name,
+                        &mut data.projects[index],
+                        upstream_project,
+                        &self.settings,
+                        &image_checks,
+                        &mut self.reveal,
+                    );
+                }
+            }
 
-                        if !self.selected_group.is_empty() {
-                            let data = locked_data.get_mut(&self.selected_group).unwrap();
-                            ui.heading(format!("Group: {}", data.name));
-                            ui.separator();
-                            ui.heading("Projects");
+            ui.separator();
+            ui.heading("Beta");
+            let row = ui.horizontal(|ui| {
+                ui.text_edit_multiline(&mut data.beta.background);
+                widgets::image_preview(ui, &data.beta.background, 100.0);
+            });
+            let target = image_import::Target {
+                group: &data.name,
+                project: None,
+                kind: FieldKind::BetaBackground,
+            };
+            image_import::drop_target(
+                ui,
+                row.response.rect,
+                &self.settings,
+                &target,
+                &mut data.beta.background,
+            );
+            image_problem_ui(
+                ui,
+                &image_checks,
+                FieldKind::BetaBackground,
+                &data.beta.background,
+            );
 
-                            ui.label("New Project Name:");
-                            ui.text_edit_singleline(&mut self.new_project_name);
-                            if ui.button("Add Project").clicked() {
-                                data.projects
-                                    .push(Project::new(self.new_project_name.clone()));
-                                self.new_project_name.clear();
-                            }
+            ui.separator();
+            ui.heading("Logo");
+            let row = ui.horizontal(|ui| {
+                ui.text_edit_multiline(&mut data.logo);
+                widgets::image_preview(ui, &data.logo, 100.0);
+            });
+            let target = image_import::Target {
+                group: &data.name,
+                project: None,
+                kind: FieldKind::Logo,
+            };
+            image_import::drop_target(
+                ui,
+                row.response.rect,
+                &self.settings,
+                &target,
+                &mut data.logo,
+            );
+            image_problem_ui(ui, &image_checks, FieldKind::Logo, &data.logo);
 
-                            let mut selected_project = self.selected_project.unwrap_or_default();
-                            ComboBox::from_id_source(Id::new("Projects"))
-                                .selected_text("Select Project")
-                                .show_ui(ui, |ui| {
-                                    for (index, project) in data.projects.iter().enumerate() {
-                                        ui.selectable_value(
-                                            &mut selected_project,
-                                            index,
-                                            project.name.clone(),
-                                        );
-                                    }
-                                });
-                            self.selected_project = Some(selected_project);
-
-                            if let Some(index) = self.selected_project {
-                                if !data.projects.is_empty() {
-                                    if index < data.projects.len() {
-                                        ui.separator();
-                                        ui.heading(format!(
-                                            "Project: {}",
-                                            data.projects[index].name
-                                        ));
-
-                                        ui.horizontal(|ui| {
-                                            ui.label("Version");
-                                            ui.text_edit_singleline(
-                                                &mut data.projects[index].version,
-                                            );
-                                        });
-                                        ui.horizontal(|ui| {
-                                            ui.label("Date");
-                                            ui.text_edit_singleline(&mut data.projects[index].date);
-                                        });
-                                        ui.horizontal(|ui| {
-                                            ui.label("Changelog");
-                                            ui.text_edit_multiline(
-                                                &mut data.projects[index].changelog,
-                                            );
-                                        });
-                                        ui.horizontal(|ui| {
-                                            ui.label("Overview");
-                                            ui.text_edit_multiline(
-                                                &mut data.projects[index].overview,
-                                            );
-                                        });
-                                        ui.horizontal(|ui| {
-                                            ui.label("Description");
-                                            ui.text_edit_multiline(
-                                                &mut data.projects[index].description,
-                                            );
-                                        });
-                                        ui.horizontal(|ui| {
-                                            ui.set_width(500.0);
-                                            ui.label("Background");
-                                            ui.text_edit_singleline(
-                                                &mut data.projects[index].background,
-                                            );
-                                            ui.add(
-                                                Image::new(
-                                                    data.projects[index]
-                                                        .background
-                                                        .clone()
-                                                        .replace("webp", "png"),
-                                                )
-                                                .max_width(400.0),
-                                            );
-                                        });
-
-                                        if let Some(package) = data.projects[index].package.as_mut()
-                                        {
-                                            ui.heading("Package");
-                                            if ui.button("Add Package").clicked() {}
-                                            ui.separator();
-                                            ui.horizontal(|ui| {
-                                                ui.label("Owner");
-                                                ui.text_edit_singleline(&mut package.owner);
-                                            });
-                                            ui.horizontal(|ui| {
-                                                ui.label("Repo Name");
-                                                ui.text_edit_singleline(&mut package.repoName);
-                                            });
-                                            ui.horizontal(|ui| {
-                                                ui.label("Version");
-                                                ui.text_edit_singleline(&mut package.version);
-                                            });
-                                            ui.horizontal(|ui| {
-                                                ui.label("File Name");
-                                                ui.text_edit_singleline(&mut package.fileName);
-                                            });
-                                        }
-                                    }
+            ui.separator();
+            if let Some(update) = data.update.as_mut() {
+                ui.checkbox(update, "Update");
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Path");
+                ui.text_edit_singleline(&mut data.path);
+            });
+            ui.separator();
+            ui.heading("Palette");
+            ui.horizontal(|ui| {
+                ui.label("Primary:");
+                ui.text_edit_singleline(&mut data.palette.primary);
+                if let Some(colour) = widgets::parse_colour(&data.palette.primary) {
+                    egui::widgets::color_picker::show_color(ui, colour, Vec2::new(50.0, 50.0));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Secondary:");
+                ui.text_edit_singleline(&mut data.palette.secondary);
+
+                if let Some(colour) = widgets::parse_colour(&data.palette.secondary) {
+                    egui::widgets::color_picker::show_color(ui, colour, Vec2::new(50.0, 50.0));
+                }
+            });
+            ui.horizontal(|ui| {
+                if data.hide.is_none() {
+                    data.hide = Some(false);
+                }
+                let mut checkbox_state = data.hide.unwrap();
+                ui.checkbox(&mut checkbox_state, "Hide Group");
+                data.hide = Some(checkbox_state);
+            });
+        }
+        self.changes_ui(ui, doc, &locked_data);
+        self.release_check_ui(ui, doc, &locked_data);
+        self.image_check_ui(ui, doc, &locked_data);
+        self.release_ui(ui.ctx(), doc, &mut locked_data);
+        self.sync_ui(ui.ctx(), doc, &mut locked_data);
+        bulk_edit_ui(ui.ctx(), doc, &mut locked_data);
+        self.submit_ui(ui.ctx(), doc, &locked_data);
+        self.page_preview
+            .ui(ui.ctx(), locked_data.get(&doc.selected_group));
+        if let Some(replacement) = self.replace.ui(ui.ctx(), &locked_data) {
+            doc.history.commit(&locked_data);
+            let result = replacement.apply(&mut locked_data);
+            doc.history.commit(&locked_data);
+            self.replace.applied(replacement, result);
+        }
+        history_ui(ui.ctx(), doc, &mut locked_data);
+        drop(locked_data);
+    }
+
+    fn release_ui(
+        &self,
+        ctx: &egui::Context,
+        doc: &mut Document,
+        groups: &mut HashMap<String, GroupData>,
+    ) {
+        let Some(wizard) = doc.release.as_mut() else {
+            return;
+        };
+        let Some(project) = groups
+            .get(&wizard.group)
+            .and_then(|group| group.projects.get(wizard.project))
+        else {
+            doc.release = None;
+            return;
+        };
+        match wizard.ui(ctx, project, self.settings.date_format()) {
+            Confirm::None => {}
+            Confirm::Cancel => doc.release = None,
+            Confirm::Apply => {
+                doc.history.commit(groups);
+                let project = &mut groups.get_mut(&wizard.group).unwrap().projects[wizard.project];
+                wizard.apply(project);
+                doc.history.commit(groups);
+                doc.release = None;
+            }
+        }
+    }
+
+    fn submit_ui(
+        &self,
+        ctx: &egui::Context,
+        doc: &mut Document,
+        groups: &HashMap<String, GroupData>,
+    ) {
+        // Captures only the fields it needs so `doc.submit` can be borrowed.
+        let client = || {
+            github::GithubClient::new(&self.settings.github_api_url).with_token(self.auth.token())
+        };
+        let Some(window) = doc.submit.as_mut() else {
+            return;
+        };
+        let broken = doc.load_report.lock().unwrap().broken.clone();
+        if !window.ui(ctx, client, &self.settings, groups, &broken) {
+            doc.submit = None;
+        }
+    }
+
+    fn sync_ui(
+        &self,
+        ctx: &egui::Context,
+        doc: &mut Document,
+        groups: &mut HashMap<String, GroupData>,
+    ) {
+        let Some(wizard) = doc.sync.as_mut() else {
+            return;
+        };
+        let Some(project) = groups
+            .get(&wizard.group)
+            .and_then(|group| group.projects.get(wizard.project))
+        else {
+            doc.sync = None;
+            return;
+        };
+        match wizard.ui(ctx, project) {
+            Confirm::None => {}
+            Confirm::Cancel => doc.sync = None,
+            Confirm::Apply => {
+                doc.history.commit(groups);
+                let project = &mut groups.get_mut(&wizard.group).unwrap().projects[wizard.project];
+                wizard.apply(project, self.settings.date_format());
+                doc.history.commit(groups);
+                doc.sync = None;
+            }
+        }
+    }
+
+    /// The document a load goes into: the shown one if nothing has been
+    /// loaded into it yet, otherwise a new tab.
+    fn open(&mut self, source: Source) -> usize {
+        let current = &self.documents[self.active];
+        let reusable = current.is_empty()
+            && !current.is_dirty()
+            && matches!(current.source, Source::New | Source::File(_));
+        if !reusable {
+            self.documents.push(Document::default());
+            self.active = self.documents.len() - 1;
+        }
+        self.documents[self.active].source = source;
+        self.active
+    }
+
+    fn close(&mut self, index: usize) {
+        self.documents.remove(index);
+        if self.documents.is_empty() {
+            self.documents.push(Document::default());
+        }
+        if self.active > index || self.active == self.documents.len() {
+            self.active -= 1;
+        }
+    }
+
+    fn tabs_ui(&mut self, ui: &mut egui::Ui) {
+        let mut close = None;
+        ui.horizontal_wrapped(|ui| {
+            for (index, document) in self.documents.iter().enumerate() {
+                ui.selectable_value(&mut self.active, index, document.title());
+                if ui.small_button("x").on_hover_text("Close").clicked() {
+                    close = Some(index);
+                }
+            }
+            if ui.button("+").on_hover_text("New document").clicked() {
+                self.documents.push(Document::default());
+                self.active = self.documents.len() - 1;
+            }
+        });
+        if let Some(index) = close {
+            if self.documents[index].is_dirty() {
+                self.closing = Some(index);
+            } else {
+                self.close(index);
+            }
+        }
+
+        let Some(index) = self.closing.filter(|index| *index < self.documents.len()) else {
+            self.closing = None;
+            return;
+        };
+        let mut decision = None;
+        Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .show(ui.ctx(), |ui| {
+                ui.label(format!(
+                    "{} has unsaved changes",
+                    self.documents[index].source.label()
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Save and close").clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        decision = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.closing = None;
+                    }
+                });
+            });
+        match decision {
+            Some(true) => match self.documents[index].save() {
+                Ok(_) => {
+                    self.closing = None;
+                    self.close(index);
+                }
+                Err(e) => eprintln!("{}", e),
+            },
+            Some(false) => {
+                self.closing = None;
+                self.close(index);
+            }
+            None => {}
+        }
+    }
+
+    /// Copies or moves the selected group or project to another document.
+    fn transfer_ui(&mut self, ui: &mut egui::Ui) {
+        if self.documents.len() < 2 {
+            return;
+        }
+        egui::CollapsingHeader::new("Copy to another document")
+            .id_source("transfer")
+            .show(ui, |ui| {
+                let source = &self.documents[self.active];
+                let group = source.selected_group.clone();
+                let project = {
+                    let groups = source.group_data.lock().unwrap();
+                    let Some(data) = groups.get(&group) else {
+                        ui.label("Select a group to copy it or one of its projects");
+                        return;
+                    };
+                    source.selected_project.and_then(|index| {
+                        data.projects
+                            .get(index)
+                            .map(|project| (index, project.name.clone()))
+                    })
+                };
+                if self.transfer_target == self.active
+                    || self.transfer_target >= self.documents.len()
+                {
+                    self.transfer_target = usize::from(self.active == 0);
+                }
+                ui.horizontal(|ui| {
+                    ui.label("To");
+                    ComboBox::from_id_source("transfer_target")
+                        .selected_text(self.documents[self.transfer_target].title())
+                        .show_ui(ui, |ui| {
+                            for (index, document) in self.documents.iter().enumerate() {
+                                if index != self.active {
+                                    ui.selectable_value(
+                                        &mut self.transfer_target,
+                                        index,
+                                        document.title(),
+                                    );
                                 }
                             }
+                        });
+                });
 
-                            ui.separator();
-                            ui.heading("Beta");
-                            ui.horizontal(|ui| {
-                                ui.text_edit_multiline(&mut data.beta.background);
-                                ui.add(
-                                    Image::new(data.beta.background.clone().replace("webp", "png"))
-                                        .max_width(100.0),
-                                );
+                let mut request = None;
+                ui.horizontal(|ui| {
+                    if ui.button(format!("Copy group {}", group)).clicked() {
+                        request = Some((Item::Group(group.clone()), false));
+                    }
+                    if ui.button(format!("Move group {}", group)).clicked() {
+                        request = Some((Item::Group(group.clone()), true));
+                    }
+                });
+
+                if let Some((index, name)) = project {
+                    let mut target_groups: Vec<String> = self.documents[self.transfer_target]
+                        .group_data
+                        .lock()
+                        .unwrap()
+                        .keys()
+                        .cloned()
+                        .collect();
+                    target_groups.sort();
+                    if !target_groups.contains(&self.transfer_group) {
+                        self.transfer_group = if target_groups.contains(&group) {
+                            group.clone()
+                        } else {
+                            target_groups.first().cloned().unwrap_or_default()
+                        };
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Project {} into group", name));
+                        ComboBox::from_id_source("transfer_group")
+                            .selected_text(&self.transfer_group)
+                            .show_ui(ui, |ui| {
+                                for target in &target_groups {
+                                    ui.selectable_value(
+                                        &mut self.transfer_group,
+                                        target.clone(),
+                                        target,
+                                    );
+                                }
                             });
+                        let item = Item::Project {
+                            group: group.clone(),
+                            index,
+                        };
+                        let enabled = !target_groups.is_empty();
+                        if ui.add_enabled(enabled, egui::Button::new("Copy")).clicked() {
+                            request = Some((item.clone(), false));
+                        }
+                        if ui.add_enabled(enabled, egui::Button::new("Move")).clicked() {
+                            request = Some((item, true));
+                        }
+                    });
+                }
 
-                            ui.separator();
-                            ui.heading(format!("Logo"));
-                            ui.horizontal(|ui| {
-                                ui.text_edit_multiline(&mut data.logo);
+                if let Some((item, remove)) = request {
+                    let (from, to) =
+                        workspace::pair_mut(&mut self.documents, self.active, self.transfer_target);
+                    self.transfer_error =
+                        workspace::transfer(from, to, &item, &self.transfer_group, remove).err();
+                }
+                if let Some(e) = &self.transfer_error {
+                    ui.colored_label(Color32::LIGHT_RED, e);
+                }
+            });
+    }
+
+    /// Client for the configured API, authenticated when signed in.
+    fn github_client(&self) -> github::GithubClient {
+        github::GithubClient::new(&self.settings.github_api_url).with_token(self.auth.token())
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Settings")
+            .id_source("settings")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Date format");
+                    ui.text_edit_singleline(&mut self.settings.date_format);
+                    if date::is_valid_format(&self.settings.date_format) {
+                        ui.label(format!(
+                            "e.g. {}",
+                            chrono::Local::now()
+                                .date_naive()
+                                .format(&self.settings.date_format)
+                        ));
+                    } else {
+                        ui.colored_label(Color32::LIGHT_RED, "Invalid format, using %Y-%m-%d");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("GitHub API URL");
+                    ui.text_edit_singleline(&mut self.settings.github_api_url);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("GitHub login URL");
+                    ui.text_edit_singleline(&mut self.settings.github_login_url);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("OAuth client ID");
+                    ui.text_edit_singleline(&mut self.settings.github_client_id);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Local checkout");
+                    ui.text_edit_singleline(&mut self.settings.checkout_path);
+                    ui.label("remote");
+                    ui.text_edit_singleline(&mut self.settings.git_remote);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Groups repository");
+                    ui.text_edit_singleline(&mut self.settings.groups_repo);
+                    ui.label("branch");
+                    ui.text_edit_singleline(&mut self.settings.groups_branch);
+                    ui.label("path");
+                    ui.text_edit_singleline(&mut self.settings.groups_path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Assets checkout");
+                    ui.text_edit_singleline(&mut self.settings.assets_checkout);
+                    ui.label("served from");
+                    ui.text_edit_singleline(&mut self.settings.assets_url);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Imported image path");
+                    ui.text_edit_singleline(&mut self.settings.assets_path);
+                    ui.label("WebP quality");
+                    ui.add(egui::Slider::new(
+                        &mut self.settings.webp_quality,
+                        0.0..=100.0,
+                    ));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Thumbnail cache limit (MB)");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.thumbnail_cache_mb)
+                            .clamp_range(0..=1024 * 1024),
+                    );
+                    self.thumbnails
+                        .set_limit(self.settings.thumbnail_cache_bytes());
+                    let used = self.thumbnails.size().div_ceil(1024 * 1024);
+                    if ui
+                        .button(format!("Clear thumbnail cache ({} MB)", used))
+                        .clicked()
+                    {
+                        match self.thumbnails.clear() {
+                            Ok(()) => ui.ctx().forget_all_images(),
+                            Err(e) => eprintln!("{}", e),
+                        }
+                    }
+                });
+                egui::CollapsingHeader::new("Image rules")
+                    .id_source("image_rules")
+                    .show(ui, |ui| {
+                        egui::Grid::new("image_rules_grid").show(ui, |ui| {
+                            for heading in [
+                                "Field",
+                                "Aspect ratio",
+                                "Max KB",
+                                "Min width",
+                                "Max width",
+                                "",
+                            ] {
+                                ui.strong(heading);
+                            }
+                            ui.end_row();
+                            for kind in FieldKind::ALL {
+                                let rule = self.settings.image_rules.get_mut(kind);
+                                ui.label(kind.label());
                                 ui.add(
-                                    Image::new(data.logo.clone().replace("webp", "png"))
-                                        .max_width(100.0),
+                                    TextEdit::singleline(&mut rule.aspect_ratio)
+                                        .desired_width(60.0),
                                 );
-                            });
-
-                            ui.separator();
-                            if let Some(mut data) = data.update.as_mut() {
-                                ui.checkbox(&mut data, "Update");
+                                ui.add(egui::DragValue::new(&mut rule.max_kb));
+                                ui.add(egui::DragValue::new(&mut rule.min_width));
+                                ui.add(egui::DragValue::new(&mut rule.max_width));
+                                match rule.aspect() {
+                                    Ok(_) => ui.label(""),
+                                    Err(e) => ui.colored_label(Color32::LIGHT_RED, e),
+                                };
+                                ui.end_row();
                             }
-                            ui.separator();
-                            ui.horizontal(|ui| {
-                                ui.label("Path");
-                                ui.text_edit_singleline(&mut data.path);
-                            });
-                            ui.separator();
-                            ui.heading("Palette");
-                            ui.horizontal(|ui| {
-                                ui.label(format!("Primary:"));
-                                ui.text_edit_singleline(&mut data.palette.primary);
-                                if !data.palette.primary.is_empty() {
-                                    let rgb = hex_to_rgb(&data.palette.primary);
-                                    egui::widgets::color_picker::show_color(
-                                        ui,
-                                        Color32::from_rgb(rgb.0, rgb.1, rgb.2),
-                                        Vec2::new(50.0, 50.0),
-                                    );
-                                }
-                            });
-                            ui.horizontal(|ui| {
-                                ui.label(format!("Secondary:"));
-                                ui.text_edit_singleline(&mut data.palette.secondary);
-
-                                if !data.palette.secondary.is_empty() {
-                                    let rgb = hex_to_rgb(&data.palette.secondary);
-                                    egui::widgets::color_picker::show_color(
-                                        ui,
-                                        Color32::from_rgb(rgb.0, rgb.1, rgb.2),
-                                        Vec2::new(50.0, 50.0),
-                                    );
+                        });
+                        ui.label("Empty or 0 means no limit");
+                    });
+                if ui.button("Save settings").clicked() {
+                    if let Err(e) = self.settings.save() {
+                        eprintln!("{}", e);
+                    }
+                }
+            });
+    }
+
+    fn changes_ui(&self, ui: &mut egui::Ui, doc: &Document, groups: &HashMap<String, GroupData>) {
+        egui::CollapsingHeader::new("Changes")
+            .id_source("changes")
+            .show(ui, |ui| {
+                let upstream = doc.upstream.lock().unwrap();
+                if upstream.is_empty() {
+                    ui.label("Fetch the group data from the repo or load it from the local checkout to compare against it");
+                    return;
+                }
+                let diff = diff::Diff::new(&upstream, groups);
+                if diff.is_empty() {
+                    ui.label("No changes");
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Copy as Markdown").clicked() {
+                        ui.output_mut(|o| o.copied_text = diff.to_markdown());
+                    }
+                    if ui.button("Copy as commit message").clicked() {
+                        ui.output_mut(|o| o.copied_text = diff.commit_message());
+                    }
+                });
+                ui.strong(diff.title());
+                for group in &diff.groups {
+                    ui.label(group.to_string());
+                }
+            });
+    }
+
+    fn release_check_ui(
+        &self,
+        ui: &mut egui::Ui,
+        doc: &Document,
+        groups: &HashMap<String, GroupData>,
+    ) {
+        let state = doc.release_checks.lock().unwrap().clone();
+        egui::CollapsingHeader::new("Release assets")
+            .id_source("release_assets")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !state.is_running(),
+                            egui::Button::new("Check release assets"),
+                        )
+                        .clicked()
+                    {
+                        let client = self.github_client();
+                        let groups = groups.clone();
+                        task::spawn(ui.ctx(), &doc.release_checks, async move {
+                            release_check::check_groups(&client, &groups).await
+                        });
+                    }
+                    if state.is_running() {
+                        ui.spinner();
+                    }
+                });
+                let Task::Done(checks) = state else {
+                    return;
+                };
+                for check in checks {
+                    ui.label(format!(
+                        "{} / {} ({}/{} {} {})",
+                        check.group,
+                        check.project,
+                        check.package.owner,
+                        check.package.repoName,
+                        check.package.version,
+                        check.package.fileName
+                    ));
+                    for finding in &check.findings {
+                        let colour = match finding {
+                            Finding::Ok { .. } => Color32::LIGHT_GREEN,
+                            Finding::NewerRelease { .. } => Color32::YELLOW,
+                            _ => Color32::LIGHT_RED,
+                        };
+                        ui.colored_label(colour, format!("    {}", finding));
+                    }
+                }
+            });
+    }
+
+    /// Downloads every referenced image and lists what it is, how big it is
+    /// and what breaks the rules for its field.
+    fn image_check_ui(
+        &self,
+        ui: &mut egui::Ui,
+        doc: &Document,
+        groups: &HashMap<String, GroupData>,
+    ) {
+        let state = doc.image_checks.lock().unwrap().clone();
+        egui::CollapsingHeader::new("Images")
+            .id_source("image_urls")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!state.is_running(), egui::Button::new("Check images"))
+                        .clicked()
+                    {
+                        let groups = groups.clone();
+                        let rules = self.settings.image_rules.clone();
+                        task::spawn(ui.ctx(), &doc.image_checks, async move {
+                            image_check::check_groups(image_check::client(), &groups, &rules).await
+                        });
+                    }
+                    if state.is_running() {
+                        ui.spinner();
+                    }
+                });
+                let Task::Done(checks) = state else {
+                    return;
+                };
+                let broken = checks.iter().filter(|check| check.is_broken()).count();
+                let flagged = checks
+                    .iter()
+                    .filter(|check| !check.is_broken() && !check.problems().is_empty())
+                    .count();
+                ui.label(format!(
+                    "{} images checked, {} broken, {} with warnings",
+                    checks.len(),
+                    broken,
+                    flagged
+                ));
+                egui::Grid::new("image_inspector")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for heading in ["Field", "Format", "Size", "Dimensions", "Aspect", ""] {
+                            ui.strong(heading);
+                        }
+                        ui.end_row();
+                        for check in &checks {
+                            let info = &check.info;
+                            ui.label(check.field.to_string())
+                                .on_hover_text(&check.field.url);
+                            ui.label(info.format.as_deref().unwrap_or("-"));
+                            ui.label(info.size.map_or("-".to_string(), |size| {
+                                format!("{} KB", size.div_ceil(1024))
+                            }));
+                            ui.label(
+                                info.dimensions
+                                    .map_or("-".to_string(), |(w, h)| format!("{}x{}", w, h)),
+                            );
+                            ui.label(info.aspect_ratio().unwrap_or("-".to_string()));
+                            ui.vertical(|ui| {
+                                let problems = check.problems();
+                                if problems.is_empty() {
+                                    ui.colored_label(Color32::LIGHT_GREEN, "ok");
                                 }
-                            });
-                            ui.horizontal(|ui| {
-                                if data.hide.is_none() {
-                                    data.hide = Some(false);
+                                for (severity, message) in problems {
+                                    ui.colored_label(severity_colour(severity), message);
                                 }
-                                let mut checkbox_state = data.hide.unwrap();
-                                ui.checkbox(&mut checkbox_state, "Hide Group");
-                                data.hide = Some(checkbox_state);
                             });
+                            ui.end_row();
                         }
-                        drop(locked_data);
+                    });
+            });
+    }
+
+    fn load_problems_ui(&self, ui: &mut egui::Ui, doc: &mut Document) {
+        let report = doc.load_report.lock().unwrap().clone();
+        if report.has_problems() {
+            let title = if report.error.is_some() {
+                "Load problems (the document could not be loaded)".to_string()
+            } else {
+                format!(
+                    "Load problems ({} issues, {} groups not loaded, {} dates normalised)",
+                    report.issues.len(),
+                    report.broken.len(),
+                    report.normalised_dates.len()
+                )
+            };
+            egui::CollapsingHeader::new(title)
+                .id_source("load_problems")
+                .default_open(report.error.is_some())
+                .show(ui, |ui| {
+                    if let Some(error) = &report.error {
+                        ui.colored_label(severity_colour(Severity::Error), error);
+                    }
+                    for issue in &report.issues {
+                        ui.colored_label(severity_colour(issue.severity), issue.to_string());
+                    }
+                    for change in &report.normalised_dates {
+                        let text = format!(
+                            "{} / {}: date \"{}\" normalised to \"{}\"",
+                            change.group, change.project, change.from, change.to
+                        );
+                        if change.ambiguous {
+                            ui.colored_label(
+                                severity_colour(Severity::Warning),
+                                format!("{} (ambiguous, read day-first; check it)", text),
+                            );
+                        } else {
+                            ui.label(text);
+                        }
+                    }
+                    let mut broken: Vec<&String> = report.broken.keys().collect();
+                    broken.sort();
+                    for name in broken {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Group \"{}\" was not loaded", name));
+                            if ui.button("Open in raw editor").clicked() {
+                                doc.repair = Some(Repair::new(
+                                    name.clone(),
+                                    report.broken[name].clone(),
+                                    self.settings.date_format(),
+                                ));
+                            }
+                        });
                     }
                 });
+        }
+
+        let Some(repair) = doc.repair.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut applied = false;
+        Window::new(format!("Repair {}", repair.name))
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ui.ctx(), |ui| {
+                for issue in &repair.issues {
+                    ui.colored_label(severity_colour(issue.severity), issue.to_string());
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(500.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            TextEdit::multiline(&mut repair.text)
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                if ui.button("Apply").clicked() {
+                    match validate::load_group(&repair.text, self.settings.date_format()) {
+                        Ok((data, _)) => {
+                            doc.group_data
+                                .lock()
+                                .unwrap()
+                                .insert(repair.name.clone(), data);
+                            let mut report = doc.load_report.lock().unwrap();
+                            report.broken.remove(&repair.name);
+                            report
+                                .issues
+                                .retain(|i| i.group.as_ref() != Some(&repair.name));
+                            applied = true;
+                        }
+                        Err(issues) => repair.issues = issues,
+                    }
+                }
+            });
+        if !open || applied {
+            doc.repair = None;
+        }
+    }
+}
+
+fn bulk_edit_ui(ctx: &egui::Context, doc: &mut Document, groups: &mut HashMap<String, GroupData>) {
+    let Some(window) = doc.bulk_edit.as_mut() else {
+        return;
+    };
+    match window.ui(ctx, groups) {
+        Confirm::None => {}
+        Confirm::Cancel => doc.bulk_edit = None,
+        Confirm::Apply => {
+            doc.history.commit(groups);
+            window.apply(groups);
+            doc.history.commit(groups);
+            doc.bulk_edit = None;
+        }
+    }
+}
+
+/// Folds finished edits into the undo history and handles the undo/redo
+/// shortcuts. Nothing is recorded while a field is being edited, so each
+/// field edit becomes one step.
+fn history_ui(ctx: &egui::Context, doc: &mut Document, groups: &mut HashMap<String, GroupData>) {
+    let editing = ctx.memory(|m| m.focused().is_some()) || ctx.input(|i| i.pointer.any_down());
+    if editing {
+        return;
+    }
+    let redo = ctx.input_mut(|i| {
+        i.consume_shortcut(&KeyboardShortcut::new(
+            Modifiers::COMMAND | Modifiers::SHIFT,
+            Key::Z,
+        )) || i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Y))
+    });
+    let undo =
+        ctx.input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z)));
+    if redo {
+        doc.history.redo(groups);
+    } else if undo {
+        doc.history.undo(groups);
+    } else {
+        doc.history.commit(groups);
+    }
+}
+
+fn project_ui(
+    ui: &mut egui::Ui,
+    group: &str,
+    project: &mut Project,
+    upstream: Option<&Project>,
+    settings: &Settings,
+    image_checks: &[ImageCheck],
+    reveal: &mut Option<Reveal>,
+) {
+    let date_format = settings.date_format();
+    let row = ui.horizontal(|ui| {
+        ui.label("Version");
+        let bumped = widgets::version_edit(ui, &mut project.version, upstream.map(|p| &p.version));
+        if bumped.is_some() {
+            let today = chrono::Local::now().date_naive().format(date_format);
+            let mut changelog = Changelog::parse(&project.changelog);
+            changelog.start_entry(&project.version.to_string(), &today.to_string());
+            project.changelog = changelog.to_markdown();
+        }
+    });
+    search::reveal(reveal, ui, row.response.rect, search::Field::Version);
+    ui.horizontal(|ui| {
+        ui.label("Date");
+        widgets::date_edit(ui, "project_date", &mut project.date, date_format);
+    });
+    let row = ui.scope(|ui| widgets::markdown_edit(ui, "Changelog", &mut project.changelog));
+    search::reveal(reveal, ui, row.response.rect, search::Field::Changelog);
+    egui::CollapsingHeader::new("Structured changelog")
+        .id_source("structured_changelog")
+        .show(ui, |ui| changelog::editor_ui(ui, &mut project.changelog));
+    let row = ui.scope(|ui| widgets::markdown_edit(ui, "Overview", &mut project.overview));
+    search::reveal(reveal, ui, row.response.rect, search::Field::Overview);
+    let row = ui.scope(|ui| widgets::markdown_edit(ui, "Description", &mut project.description));
+    search::reveal(reveal, ui, row.response.rect, search::Field::Description);
+    let row = ui.horizontal(|ui| {
+        ui.set_width(500.0);
+        ui.label("Background");
+        ui.text_edit_singleline(&mut project.background);
+        widgets::image_preview(ui, &project.background, 400.0);
+    });
+    let target = image_import::Target {
+        group,
+        project: Some(&project.name),
+        kind: FieldKind::Background,
+    };
+    image_import::drop_target(
+        ui,
+        row.response.rect,
+        settings,
+        &target,
+        &mut project.background,
+    );
+    image_problem_ui(ui, image_checks, FieldKind::Background, &project.background);
+
+    if let Some(package) = project.package.as_mut() {
+        ui.heading("Package");
+        ui.separator();
+        if package.version != project.version {
+            ui.colored_label(
+                Color32::YELLOW,
+                format!(
+                    "Package version {} does not match project version {}",
+                    package.version, project.version
+                ),
+            );
+        }
+        let row = ui.horizontal(|ui| {
+            ui.label("Owner");
+            ui.text_edit_singleline(&mut package.owner);
+        });
+        search::reveal(reveal, ui, row.response.rect, search::Field::PackageOwner);
+        let row = ui.horizontal(|ui| {
+            ui.label("Repo Name");
+            ui.text_edit_singleline(&mut package.repoName);
+        });
+        search::reveal(reveal, ui, row.response.rect, search::Field::PackageRepo);
+        let row = ui.horizontal(|ui| {
+            ui.label("Version");
+            let upstream = upstream
+                .and_then(|p| p.package.as_ref())
+                .map(|p| &p.version);
+            widgets::version_edit(ui, &mut package.version, upstream);
         });
+        search::reveal(reveal, ui, row.response.rect, search::Field::PackageVersion);
+        let row = ui.horizontal(|ui| {
+            ui.label("File Name");
+            ui.text_edit_singleline(&mut package.fileName);
+        });
+        search::reveal(reveal, ui, row.response.rect, search::Field::PackageFile);
+    } else if ui.button("Add Package").clicked() {
+        project.package = Some(Package::default());
+    }
+}
+
+fn severity_colour(severity: Severity) -> Color32 {
+    match severity {
+        Severity::Error => Color32::LIGHT_RED,
+        Severity::Warning => Color32::YELLOW,
+    }
+}
+
+/// Shows what the last image check found wrong with the image in a `kind`
+/// field, if anything.
+fn image_problem_ui(ui: &mut egui::Ui, checks: &[ImageCheck], kind: FieldKind, url: &str) {
+    for (severity, message) in image_check::problems_for(checks, kind, url) {
+        ui.colored_label(severity_colour(severity), format!("Image: {}", message));
     }
 }
 
-async fn fetch_data() -> Result<HashMap<String, GroupData>, String> {
-    let link = "https://raw.githubusercontent.com/infinity-MSFS/groups/main/groups.json";
+const GROUPS_URL: &str = "https://raw.githubusercontent.com/infinity-MSFS/groups/main/groups.json";
 
+async fn fetch_data(link: &str, date_format: &str) -> Result<LoadReport, String> {
     match reqwest::get(link).await {
-        Ok(request) => match request.json::<HashMap<String, GroupData>>().await {
-            Ok(data) => Ok(data),
-            Err(e) => Err(format!("Error deserializing: {}", e)),
+        Ok(request) => match request.text().await {
+            Ok(text) => validate::load_groups(&text, date_format).map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Error reading response: {}", e)),
         },
         Err(e) => Err(format!("Error fetching: {}", e)),
     }
 }
 
-fn write_locked_data(data: HashMap<String, GroupData>) {
-    let json_data = serde_json::to_vec_pretty(&data).unwrap();
-
-    let mut file = File::create("groups.json").expect("failed to create file");
-
-    file.write_all(&json_data).expect("write error");
+fn read_groups_file(path: &std::path::Path, date_format: &str) -> Result<LoadReport, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+    validate::load_groups(&text, date_format).map_err(|e| e.to_string())
 }
 
 fn custom_window_frame(ctx: &egui::Context, title: &str, add_contents: impl FnOnce(&mut egui::Ui)) {
@@ -442,11 +1458,3 @@ fn close_maximize_minimize(ui: &mut egui::Ui) {
         ui.ctx().send_viewport_cmd(ViewportCommand::Minimized(true));
     }
 }
-
-fn hex_to_rgb(hex: &str) -> (u8, u8, u8) {
-    let r = u8::from_str_radix(&hex[1..3], 16).ok().unwrap();
-    let g = u8::from_str_radix(&hex[3..5], 16).ok().unwrap();
-    let b = u8::from_str_radix(&hex[5..7], 16).ok().unwrap();
-
-    (r, g, b)
-}
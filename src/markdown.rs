@@ -0,0 +1,462 @@
+use eframe::egui::{self, Color32, Image, RichText};
+use pulldown_cmark::{BrokenLink, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
+
+use crate::validate::line_column;
+
+/// The Markdown dialect the website renders: CommonMark plus the GitHub
+/// extensions for tables, strikethrough and task lists.
+fn site_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+/// Extensions the site does not render. Text is parsed with these on as well
+/// so we can warn when one of them is used.
+fn unsupported_options() -> Options {
+    Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_HEADING_ATTRIBUTES
+        | Options::ENABLE_MATH
+        | Options::ENABLE_DEFINITION_LIST
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Finds links the site can't follow and syntax it won't render.
+pub fn check(text: &str) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut warn = |offset: usize, message: String| {
+        warnings.push(Warning {
+            line: line_column(text, offset).0,
+            message,
+        })
+    };
+
+    let mut broken = Vec::new();
+    let callback = |link: BrokenLink| {
+        if matches!(link.link_type, LinkType::Reference | LinkType::Collapsed) {
+            broken.push((link.span.start, link.reference.to_string()));
+        }
+        None
+    };
+    let parser = Parser::new_with_broken_link_callback(text, site_options(), Some(callback));
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Html(_) | Event::InlineHtml(_) => warn(
+                range.start,
+                "raw HTML is not rendered by the site".to_string(),
+            ),
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                if let Some(problem) = link_problem(&dest_url) {
+                    warn(range.start, format!("link {}", problem));
+                }
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                if let Some(problem) = link_problem(&dest_url) {
+                    warn(range.start, format!("image {}", problem));
+                }
+            }
+            _ => {}
+        }
+    }
+    for (offset, reference) in broken {
+        warn(
+            offset,
+            format!("link reference [{}] is not defined", reference),
+        );
+    }
+
+    let parser = Parser::new_ext(text, site_options() | unsupported_options());
+    for (event, range) in parser.into_offset_iter() {
+        let feature = match event {
+            Event::FootnoteReference(_) | Event::Start(Tag::FootnoteDefinition(_)) => "footnotes",
+            Event::InlineMath(_) | Event::DisplayMath(_) => "math",
+            Event::Start(Tag::DefinitionList) => "definition lists",
+            Event::Start(Tag::MetadataBlock(_)) => "metadata blocks",
+            Event::Start(Tag::Heading {
+                id, classes, attrs, ..
+            }) if id.is_some() || !classes.is_empty() || !attrs.is_empty() => "heading attributes",
+            _ => continue,
+        };
+        warn(
+            range.start,
+            format!("{} are not supported by the site", feature),
+        );
+    }
+
+    warnings.sort_by_key(|w| w.line);
+    warnings
+}
+
+fn link_problem(url: &str) -> Option<String> {
+    if url.trim().is_empty() {
+        Some("has no URL".to_string())
+    } else if url.contains(char::is_whitespace) {
+        Some(format!("\"{}\" contains spaces", url))
+    } else if !(url.starts_with("https://")
+        || url.starts_with("http://")
+        || url.starts_with("mailto:")
+        || url.starts_with('#'))
+    {
+        Some(format!("\"{}\" is not an absolute URL", url))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Span {
+    text: String,
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+    code: bool,
+    link: Option<String>,
+    image: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Block {
+    Heading(HeadingLevel, Vec<Span>),
+    Paragraph {
+        indent: usize,
+        quote: bool,
+        spans: Vec<Span>,
+    },
+    Code(String),
+    TableRow {
+        header: bool,
+        cells: Vec<Vec<Span>>,
+    },
+    Rule,
+}
+
+/// Flattens the event stream into blocks of styled spans, which is all the
+/// structure the preview needs.
+fn blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Span::default();
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut quote = 0;
+    let mut code: Option<String> = None;
+    let mut row: Option<Vec<Vec<Span>>> = None;
+    let mut header = false;
+
+    let flush = |spans: &mut Vec<Span>, blocks: &mut Vec<Block>, indent: usize, quote: bool| {
+        if !spans.is_empty() {
+            blocks.push(Block::Paragraph {
+                indent,
+                quote,
+                spans: std::mem::take(spans),
+            });
+        }
+    };
+
+    for event in Parser::new_ext(text, site_options()) {
+        match event {
+            Event::Start(Tag::Heading { .. }) | Event::Start(Tag::Paragraph) => {}
+            Event::End(TagEnd::Heading(level)) => {
+                blocks.push(Block::Heading(level, std::mem::take(&mut spans)));
+            }
+            Event::End(TagEnd::Paragraph) => flush(&mut spans, &mut blocks, lists.len(), quote > 0),
+            Event::Start(Tag::BlockQuote(_)) => quote += 1,
+            Event::End(TagEnd::BlockQuote(_)) => quote -= 1,
+            Event::Start(Tag::List(start)) => {
+                flush(&mut spans, &mut blocks, lists.len(), quote > 0);
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                flush(&mut spans, &mut blocks, lists.len(), quote > 0);
+                let marker = match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                spans.push(Span {
+                    text: marker,
+                    ..Default::default()
+                });
+            }
+            Event::End(TagEnd::Item) => flush(&mut spans, &mut blocks, lists.len(), quote > 0),
+            Event::TaskListMarker(done) => spans.push(Span {
+                text: if done { "☑ " } else { "☐ " }.to_string(),
+                ..Default::default()
+            }),
+            Event::Start(Tag::CodeBlock(_)) => code = Some(String::new()),
+            Event::End(TagEnd::CodeBlock) => {
+                blocks.push(Block::Code(code.take().unwrap_or_default()));
+            }
+            Event::Start(Tag::TableHead) => {
+                header = true;
+                row = Some(Vec::new());
+            }
+            Event::Start(Tag::TableRow) => row = Some(Vec::new()),
+            Event::End(TagEnd::TableHead) | Event::End(TagEnd::TableRow) => {
+                blocks.push(Block::TableRow {
+                    header,
+                    cells: row.take().unwrap_or_default(),
+                });
+                header = false;
+            }
+            Event::End(TagEnd::TableCell) => {
+                if let Some(row) = row.as_mut() {
+                    row.push(std::mem::take(&mut spans));
+                }
+            }
+            Event::Start(Tag::Strong) => style.strong = true,
+            Event::End(TagEnd::Strong) => style.strong = false,
+            Event::Start(Tag::Emphasis) => style.emphasis = true,
+            Event::End(TagEnd::Emphasis) => style.emphasis = false,
+            Event::Start(Tag::Strikethrough) => style.strikethrough = true,
+            Event::End(TagEnd::Strikethrough) => style.strikethrough = false,
+            Event::Start(Tag::Link { dest_url, .. }) => style.link = Some(dest_url.to_string()),
+            Event::End(TagEnd::Link) => style.link = None,
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                spans.push(Span {
+                    image: Some(dest_url.to_string()),
+                    ..Default::default()
+                });
+                style.image = Some(dest_url.to_string());
+            }
+            Event::End(TagEnd::Image) => style.image = None,
+            Event::Text(text) => {
+                if let Some(code) = code.as_mut() {
+                    code.push_str(&text);
+                } else if style.image.is_none() {
+                    spans.push(Span {
+                        text: text.to_string(),
+                        image: None,
+                        ..style.clone()
+                    });
+                }
+            }
+            Event::Code(text) => spans.push(Span {
+                text: text.to_string(),
+                code: true,
+                image: None,
+                ..style.clone()
+            }),
+            Event::SoftBreak => spans.push(Span {
+                text: " ".to_string(),
+                ..Default::default()
+            }),
+            Event::HardBreak => flush(&mut spans, &mut blocks, lists.len(), quote > 0),
+            Event::Rule => blocks.push(Block::Rule),
+            Event::Html(html) | Event::InlineHtml(html) => spans.push(Span {
+                text: html.to_string(),
+                code: true,
+                ..Default::default()
+            }),
+            _ => {}
+        }
+    }
+    flush(&mut spans, &mut blocks, 0, false);
+    blocks
+}
+
+fn spans_ui(ui: &mut egui::Ui, spans: &[Span], size: Option<f32>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for span in spans {
+            if let Some(url) = &span.image {
+                ui.add(Image::new(url.as_str()).max_width(300.0));
+                continue;
+            }
+            let mut text = RichText::new(&span.text);
+            if let Some(size) = size {
+                text = text.size(size).strong();
+            }
+            if span.strong {
+                text = text.strong();
+            }
+            if span.emphasis {
+                text = text.italics();
+            }
+            if span.strikethrough {
+                text = text.strikethrough();
+            }
+            if span.code {
+                text = text.code();
+            }
+            match &span.link {
+                Some(url) => {
+                    ui.hyperlink_to(text, url);
+                }
+                None => {
+                    ui.label(text);
+                }
+            }
+        }
+    });
+}
+
+/// Renders `text` roughly as the website would.
+pub fn preview(ui: &mut egui::Ui, text: &str) {
+    for block in blocks(text) {
+        match block {
+            Block::Heading(level, spans) => {
+                let size = match level {
+                    HeadingLevel::H1 => 24.0,
+                    HeadingLevel::H2 => 20.0,
+                    HeadingLevel::H3 => 17.0,
+                    _ => 15.0,
+                };
+                spans_ui(ui, &spans, Some(size));
+            }
+            Block::Paragraph {
+                indent,
+                quote,
+                spans,
+            } => {
+                ui.horizontal(|ui| {
+                    ui.add_space(indent.saturating_sub(1) as f32 * 16.0);
+                    if quote {
+                        ui.label(RichText::new("▌").color(Color32::GRAY));
+                    }
+                    spans_ui(ui, &spans, None);
+                });
+            }
+            Block::Code(code) => {
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.label(RichText::new(code.trim_end()).code());
+                });
+            }
+            Block::TableRow { header, cells } => {
+                ui.horizontal(|ui| {
+                    for cell in cells {
+                        let cell: Vec<Span> = cell
+                            .into_iter()
+                            .map(|span| Span {
+                                strong: span.strong || header,
+                                ..span
+                            })
+                            .collect();
+                        ui.group(|ui| spans_ui(ui, &cell, None));
+                    }
+                });
+            }
+            Block::Rule => {
+                ui.separator();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The only warning `text` gives, as (line, message).
+    fn warning(text: &str) -> (usize, String) {
+        let warnings = check(text);
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        (warnings[0].line, warnings[0].message.clone())
+    }
+
+    #[test]
+    fn clean_input() {
+        let text = "# Title\n\nSome **bold**, _emphasis_, ~~struck~~ and `code`.\n\n\
+                    - [x] done\n- [ ] to do\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n\
+                    [Site](https://example.com), [mail](mailto:a@example.com), \
+                    [top](#title), [ref][r] and ![logo](https://example.com/logo.png)\n\n\
+                    [r]: https://example.com/ref\n";
+        assert!(check(text).is_empty(), "{:?}", check(text));
+        assert!(check("").is_empty());
+    }
+
+    #[test]
+    fn raw_html() {
+        assert_eq!(
+            warning("text\n\n<div>block</div>\n"),
+            (3, "raw HTML is not rendered by the site".to_string())
+        );
+        assert_eq!(warning("inline <b>bold").0, 1);
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            warning("[relative](docs/page.md)"),
+            (
+                1,
+                "link \"docs/page.md\" is not an absolute URL".to_string()
+            )
+        );
+        assert_eq!(warning("line\n[empty]()").1, "link has no URL");
+        assert_eq!(
+            warning("[spaced](<https://example.com/a b>)").1,
+            "link \"https://example.com/a b\" contains spaces"
+        );
+    }
+
+    #[test]
+    fn images() {
+        assert_eq!(
+            warning("intro\n\n![logo](logo.png)"),
+            (3, "image \"logo.png\" is not an absolute URL".to_string())
+        );
+    }
+
+    #[test]
+    fn undefined_references() {
+        assert_eq!(
+            warning("see [the docs][docs]"),
+            (1, "link reference [docs] is not defined".to_string())
+        );
+        // A shortcut reference that isn't defined is just text.
+        assert!(check("[not a link]").is_empty());
+    }
+
+    #[test]
+    fn headings_with_attributes() {
+        assert_eq!(
+            warning("text\n\n# Heading {#id .class}"),
+            (
+                3,
+                "heading attributes are not supported by the site".to_string()
+            )
+        );
+        assert!(check("# Heading").is_empty());
+    }
+
+    #[test]
+    fn unsupported_extensions() {
+        // Without footnotes the site reads the definition as a link target.
+        let footnotes: Vec<_> = check("text[^1]\n\n[^1]: note")
+            .into_iter()
+            .map(|w| (w.line, w.message))
+            .collect();
+        assert_eq!(
+            footnotes,
+            [
+                (1, "link \"note\" is not an absolute URL".to_string()),
+                (1, "footnotes are not supported by the site".to_string()),
+                (3, "footnotes are not supported by the site".to_string()),
+            ]
+        );
+        assert_eq!(
+            warning("area is $x^2$").1,
+            "math are not supported by the site"
+        );
+        assert_eq!(
+            warning("Term\n: definition").1,
+            "definition lists are not supported by the site"
+        );
+        assert_eq!(
+            warning("---\ntitle: x\n---\n\nbody"),
+            (
+                1,
+                "metadata blocks are not supported by the site".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! A throwaway HTTP server standing in for the GitHub API in tests.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A canned response for requests with this method and path (query included).
+pub struct Route {
+    method: &'static str,
+    path: String,
+    status: u16,
+    body: String,
+}
+
+pub fn route(method: &'static str, path: &str, status: u16, body: impl ToString) -> Route {
+    Route {
+        method,
+        path: path.to_string(),
+        status,
+        body: body.to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+/// Answers every request with the first matching route, or a 404, and
+/// records what it was sent. The server lives until the test exits.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                if let Some(request) = respond(stream, &routes) {
+                    log.lock().unwrap().push(request);
+                }
+            }
+        });
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn respond(mut stream: TcpStream, routes: &[Route]) -> Option<Request> {
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).ok()?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+
+    let (status, response) = routes
+        .iter()
+        .find(|route| route.method == method && route.path == path)
+        .map_or((404, r#"{"message":"Not Found"}"#), |route| {
+            (route.status, route.body.as_str())
+        });
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        response.len()
+    );
+    stream.write_all(head.as_bytes()).ok()?;
+    stream.write_all(response.as_bytes()).ok()?;
+    Some(Request {
+        method,
+        path,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
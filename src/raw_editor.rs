@@ -0,0 +1,305 @@
+use eframe::egui::{self, text::LayoutJob, Color32, FontId, TextEdit, TextFormat};
+use std::collections::HashMap;
+
+use crate::types::{self, GroupData};
+use crate::validate::{self, Issue, Severity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawScope {
+    Group,
+    File,
+}
+
+/// Raw JSON view of the selected group or the whole file. Valid edits are
+/// written straight back into the group data, and changes made in the form
+/// editor are picked up the next time the text is not being edited.
+#[derive(Debug, Clone)]
+pub struct RawEditor {
+    scope: RawScope,
+    target: String,
+    text: String,
+    last_synced: String,
+    cursor: Option<usize>,
+    issues: Vec<Issue>,
+}
+
+impl Default for RawEditor {
+    fn default() -> Self {
+        Self {
+            scope: RawScope::Group,
+            target: String::new(),
+            text: String::new(),
+            last_synced: String::new(),
+            cursor: None,
+            issues: Vec::new(),
+        }
+    }
+}
+
+impl RawEditor {
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        groups: &mut HashMap<String, GroupData>,
+        selected_group: &str,
+        date_format: &str,
+    ) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.scope, RawScope::Group, "Selected group");
+            ui.selectable_value(&mut self.scope, RawScope::File, "Whole file");
+        });
+
+        let target = match self.scope {
+            RawScope::Group => selected_group.to_string(),
+            RawScope::File => String::new(),
+        };
+        if self.scope == RawScope::Group && !groups.contains_key(&target) {
+            ui.label("Select a group to edit it as JSON");
+            return;
+        }
+
+        let current = serialize(groups, self.scope, &target);
+        if target != self.target || current != self.last_synced {
+            self.target = target;
+            self.text = current.clone();
+            self.last_synced = current;
+            self.issues.clear();
+        }
+
+        let cursor = self.cursor;
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut job = highlight(text, cursor, &ui.style().visuals);
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|f| f.layout_job(job))
+        };
+        let output = TextEdit::multiline(&mut self.text)
+            .code_editor()
+            .desired_width(f32::INFINITY)
+            .desired_rows(30)
+            .layouter(&mut layouter)
+            .show(ui);
+
+        self.cursor = output
+            .cursor_range
+            .map(|range| char_to_byte(&self.text, range.primary.ccursor.index));
+
+        if output.response.changed() {
+            self.apply(groups, date_format);
+        }
+
+        for issue in &self.issues {
+            let colour = match issue.severity {
+                Severity::Error => Color32::LIGHT_RED,
+                Severity::Warning => Color32::YELLOW,
+            };
+            ui.colored_label(colour, issue.to_string());
+        }
+    }
+
+    fn apply(&mut self, groups: &mut HashMap<String, GroupData>, date_format: &str) {
+        match self.scope {
+            RawScope::Group => match validate::load_group(&self.text, date_format) {
+                Ok((data, warnings)) => {
+                    groups.insert(self.target.clone(), data);
+                    self.issues = warnings;
+                }
+                Err(issues) => self.issues = issues,
+            },
+            // A file with errors would replace the document with only the
+            // groups that loaded, or none at all, so it is never applied.
+            RawScope::File => match validate::load_groups(&self.text, date_format) {
+                Ok(report)
+                    if report.broken.is_empty()
+                        && report.issues.iter().all(|i| i.severity != Severity::Error) =>
+                {
+                    *groups = report.groups;
+                    self.issues = report.issues;
+                }
+                Ok(report) => self.issues = report.issues,
+                Err(issue) => self.issues = vec![issue],
+            },
+        }
+        self.last_synced = serialize(groups, self.scope, &self.target);
+    }
+}
+
+fn serialize(groups: &HashMap<String, GroupData>, scope: RawScope, target: &str) -> String {
+    match scope {
+        RawScope::Group => serde_json::to_string_pretty(&groups[target]).unwrap(),
+        RawScope::File => types::groups_json(groups),
+    }
+}
+
+fn char_to_byte(text: &str, index: usize) -> usize {
+    text.char_indices()
+        .nth(index)
+        .map_or(text.len(), |(byte, _)| byte)
+}
+
+#[derive(Clone, Copy)]
+enum Token {
+    Key,
+    String,
+    Number,
+    Literal,
+    Punctuation,
+    Whitespace,
+}
+
+/// Splits JSON text into highlightable tokens. Malformed input is still
+/// tokenized so highlighting keeps working while the text is being typed.
+fn tokenize(text: &str) -> Vec<(Token, std::ops::Range<usize>)> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        let token = match bytes[pos] {
+            b'"' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' && bytes[pos] != b'\n' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+                while !text.is_char_boundary(pos) {
+                    pos += 1;
+                }
+                let rest = text[pos..].trim_start_matches([' ', '\t']);
+                if rest.starts_with(':') {
+                    Token::Key
+                } else {
+                    Token::String
+                }
+            }
+            b'{' | b'}' | b'[' | b']' | b':' | b',' => {
+                pos += 1;
+                Token::Punctuation
+            }
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t' | b'\n' | b'\r') {
+                    pos += 1;
+                }
+                Token::Whitespace
+            }
+            b'-' | b'0'..=b'9' => {
+                while pos < bytes.len()
+                    && matches!(bytes[pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                {
+                    pos += 1;
+                }
+                Token::Number
+            }
+            _ => {
+                while pos < bytes.len() && !is_delimiter(bytes[pos]) {
+                    pos += 1;
+                }
+                while !text.is_char_boundary(pos) {
+                    pos += 1;
+                }
+                Token::Literal
+            }
+        };
+        tokens.push((token, start..pos));
+    }
+    tokens
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'"' | b'{' | b'}' | b'[' | b']' | b':' | b',' | b' ' | b'\t' | b'\n' | b'\r'
+    )
+}
+
+/// Finds the bracket paired with the one at `offset`, if there is one.
+fn matching_bracket(text: &str, offset: usize) -> Option<usize> {
+    let tokens = tokenize(text);
+    let brackets: Vec<(usize, u8)> = tokens
+        .iter()
+        .filter(|(token, _)| matches!(token, Token::Punctuation))
+        .map(|(_, range)| (range.start, text.as_bytes()[range.start]))
+        .filter(|(_, b)| matches!(b, b'{' | b'}' | b'[' | b']'))
+        .collect();
+    let index = brackets.iter().position(|(pos, _)| *pos == offset)?;
+    let (_, bracket) = brackets[index];
+    let mut depth = 0i32;
+    if matches!(bracket, b'{' | b'[') {
+        for (pos, b) in &brackets[index..] {
+            depth += if matches!(b, b'{' | b'[') { 1 } else { -1 };
+            if depth == 0 {
+                return Some(*pos);
+            }
+        }
+    } else {
+        for (pos, b) in brackets[..=index].iter().rev() {
+            depth += if matches!(b, b'}' | b']') { 1 } else { -1 };
+            if depth == 0 {
+                return Some(*pos);
+            }
+        }
+    }
+    None
+}
+
+fn highlight(text: &str, cursor: Option<usize>, visuals: &egui::Visuals) -> LayoutJob {
+    let font_id = FontId::monospace(12.0);
+    let bracket_at = |offset: usize| {
+        text.as_bytes()
+            .get(offset)
+            .is_some_and(|b| matches!(b, b'{' | b'}' | b'[' | b']'))
+    };
+    let matched: Vec<usize> = cursor
+        .and_then(|c| {
+            [Some(c), c.checked_sub(1)]
+                .into_iter()
+                .flatten()
+                .find(|offset| bracket_at(*offset))
+        })
+        .and_then(|offset| matching_bracket(text, offset).map(|other| vec![offset, other]))
+        .unwrap_or_default();
+
+    let mut job = LayoutJob::default();
+    for (token, range) in tokenize(text) {
+        let colour = match token {
+            Token::Key => Color32::from_rgb(156, 220, 254),
+            Token::String => Color32::from_rgb(206, 145, 120),
+            Token::Number => Color32::from_rgb(181, 206, 168),
+            Token::Literal => Color32::from_rgb(86, 156, 214),
+            Token::Punctuation | Token::Whitespace => visuals.text_color(),
+        };
+        let mut format = TextFormat::simple(font_id.clone(), colour);
+        if matched.contains(&range.start) {
+            format.background = Color32::from_rgb(80, 80, 80);
+        }
+        job.append(&text[range], 0.0, format);
+    }
+    job
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_editor(text: &str) -> RawEditor {
+        RawEditor {
+            scope: RawScope::File,
+            text: text.to_string(),
+            ..RawEditor::default()
+        }
+    }
+
+    #[test]
+    fn whole_file_with_errors_is_not_applied() {
+        let mut groups = HashMap::from([("a".to_string(), GroupData::new("A".to_string()))]);
+        let mut editor = file_editor("[]");
+        editor.apply(&mut groups, crate::date::ISO_8601);
+        assert!(groups.contains_key("a"));
+        assert!(editor.issues.iter().any(|i| i.severity == Severity::Error));
+
+        let mut replacement = HashMap::new();
+        replacement.insert("b".to_string(), GroupData::new("B".to_string()));
+        let mut editor = file_editor(&types::groups_json(&replacement));
+        editor.apply(&mut groups, crate::date::ISO_8601);
+        assert_eq!(groups, replacement);
+    }
+}
@@ -0,0 +1,155 @@
+use eframe::egui::{self, Color32, Window};
+use std::cmp::Ordering;
+
+use crate::changelog::{Changelog, Kind};
+use crate::date::ProjectDate;
+use crate::types::Project;
+use crate::version::{Bump, Version};
+use crate::widgets::{self, Confirm};
+
+/// State of the "Release project" window for one project.
+#[derive(Debug, Clone)]
+pub struct ReleaseWizard {
+    pub group: String,
+    pub project: usize,
+    version: Version,
+    date: ProjectDate,
+    notes: [String; 3],
+    update_package: bool,
+}
+
+impl ReleaseWizard {
+    pub fn new(group: &str, index: usize, project: &Project, date_format: &str) -> Self {
+        let mut date = ProjectDate::default();
+        date.set(chrono::Local::now().date_naive(), date_format);
+        Self {
+            group: group.to_string(),
+            project: index,
+            version: project
+                .version
+                .bumped(Bump::Patch)
+                .unwrap_or_else(|_| project.version.clone()),
+            date,
+            notes: Default::default(),
+            update_package: project.package.is_some(),
+        }
+    }
+
+    /// Problems that block the release.
+    fn problems(&self, project: &Project) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.version.parse() {
+            problems.push(e);
+        } else if project.version.parse().is_ok()
+            && self.version.compare(&project.version) != Some(Ordering::Greater)
+        {
+            problems.push(format!(
+                "The new version must be higher than {}",
+                project.version
+            ));
+        }
+        if let Err(e) = self.date.parse() {
+            problems.push(e);
+        }
+        if let Some(package) = &project.package {
+            if !self.update_package && package.version != self.version {
+                problems.push(format!(
+                    "Package version {} would not match project version {}",
+                    package.version, self.version
+                ));
+            }
+        }
+        problems
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context, project: &Project, date_format: &str) -> Confirm {
+        let mut action = Confirm::None;
+        let mut open = true;
+        Window::new(format!("Release {}", project.name))
+            .id(egui::Id::new("release_wizard"))
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                ui.label(format!("Current version: {}", project.version));
+                if let Some(package) = &project.package {
+                    if package.version != project.version {
+                        ui.colored_label(
+                            Color32::YELLOW,
+                            format!(
+                                "Package version {} currently disagrees with the project version",
+                                package.version
+                            ),
+                        );
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label("New version");
+                    widgets::version_edit(ui, &mut self.version, None);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Date");
+                    widgets::date_edit(ui, "release_date", &mut self.date, date_format);
+                });
+                for (kind, notes) in Kind::ALL.into_iter().zip(self.notes.iter_mut()) {
+                    ui.label(format!("{} (one per line)", kind.label()));
+                    ui.add(
+                        egui::TextEdit::multiline(notes)
+                            .desired_rows(3)
+                            .desired_width(f32::INFINITY),
+                    );
+                }
+                if project.package.is_some() {
+                    ui.checkbox(&mut self.update_package, "Set the package version too");
+                }
+
+                let problems = self.problems(project);
+                for problem in &problems {
+                    ui.colored_label(Color32::LIGHT_RED, problem);
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(problems.is_empty(), egui::Button::new("Release"))
+                        .clicked()
+                    {
+                        action = Confirm::Apply;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Confirm::Cancel;
+                    }
+                });
+            });
+        if !open {
+            action = Confirm::Cancel;
+        }
+        action
+    }
+
+    /// Writes the version, date, changelog entry and package version in one go.
+    pub fn apply(&self, project: &mut Project) {
+        project.version = self.version.clone();
+        project.date = self.date.clone();
+
+        let version = self.version.to_string();
+        let mut changelog = Changelog::parse(&project.changelog);
+        changelog.start_entry(&version, &self.date.to_string());
+        if let Some(entry) = changelog.entry_mut(&version) {
+            entry.date = self.date.to_string();
+            for (kind, notes) in Kind::ALL.into_iter().zip(&self.notes) {
+                entry.items_mut(kind).extend(
+                    notes
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(|line| line.trim_start_matches("- ").to_string()),
+                );
+            }
+        }
+        project.changelog = changelog.to_markdown();
+
+        if self.update_package {
+            if let Some(package) = project.package.as_mut() {
+                package.version = self.version.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::github::{GithubClient, Release};
+use crate::types::{GroupData, Package};
+use crate::version::Version;
+
+#[derive(Debug, Clone)]
+pub enum Finding {
+    Ok {
+        tag: String,
+    },
+    MissingRepo {
+        owner: String,
+        repo: String,
+    },
+    MissingTag {
+        tags: Vec<String>,
+    },
+    MissingAsset {
+        tag: String,
+        file_name: String,
+        available: Vec<String>,
+    },
+    VersionMismatch {
+        package: Version,
+        project: Version,
+    },
+    NewerRelease {
+        tag: String,
+    },
+    Error(String),
+}
+
+impl Finding {
+    pub fn is_problem(&self) -> bool {
+        !matches!(self, Finding::Ok { .. })
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Ok { tag } => write!(f, "release {} has the asset", tag),
+            Finding::MissingRepo { owner, repo } => {
+                write!(f, "repository {}/{} not found", owner, repo)
+            }
+            Finding::MissingTag { tags } => {
+                write!(f, "no published release tagged {}", tags.join(" or "))
+            }
+            Finding::MissingAsset {
+                tag,
+                file_name,
+                available,
+            } => write!(
+                f,
+                "release {} has no asset {} (assets: {})",
+                tag,
+                file_name,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ),
+            Finding::VersionMismatch { package, project } => write!(
+                f,
+                "package version {} does not match project version {}",
+                package, project
+            ),
+            Finding::NewerRelease { tag } => {
+                write!(f, "a newer release {} has been published", tag)
+            }
+            Finding::Error(e) => f.write_str(e),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectCheck {
+    pub group: String,
+    pub project: String,
+    pub package: Package,
+    pub findings: Vec<Finding>,
+}
+
+/// A repository's releases as returned by `GithubClient::releases`.
+type ReleaseList = Result<Option<Vec<Release>>, String>;
+
+/// Tags a package version may have been published under.
+fn candidate_tags(version: &Version) -> Vec<String> {
+    let version = version.to_string();
+    let bare = version.trim().trim_start_matches(['v', 'V']).to_string();
+    vec![format!("v{}", bare), bare]
+}
+
+fn check_package(
+    package: &Package,
+    project_version: &Version,
+    releases: &ReleaseList,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if package.version.compare(project_version) != Some(Ordering::Equal) {
+        findings.push(Finding::VersionMismatch {
+            package: package.version.clone(),
+            project: project_version.clone(),
+        });
+    }
+
+    let releases = match releases {
+        Ok(Some(releases)) => releases,
+        Ok(None) => {
+            findings.push(Finding::MissingRepo {
+                owner: package.owner.clone(),
+                repo: package.repoName.clone(),
+            });
+            return findings;
+        }
+        Err(e) => {
+            findings.push(Finding::Error(e.clone()));
+            return findings;
+        }
+    };
+    let published: Vec<&Release> = releases.iter().filter(|r| !r.draft).collect();
+
+    let tags = candidate_tags(&package.version);
+    match published.iter().find(|r| tags.contains(&r.tag_name)) {
+        None => findings.push(Finding::MissingTag { tags }),
+        Some(release) => {
+            if release.assets.iter().any(|a| a.name == package.fileName) {
+                findings.push(Finding::Ok {
+                    tag: release.tag_name.clone(),
+                });
+            } else {
+                findings.push(Finding::MissingAsset {
+                    tag: release.tag_name.clone(),
+                    file_name: package.fileName.clone(),
+                    available: release.assets.iter().map(|a| a.name.clone()).collect(),
+                });
+            }
+        }
+    }
+
+    if let Some(latest) = latest_release(&published) {
+        if Version::new(&latest.tag_name).compare(&package.version) == Some(Ordering::Greater) {
+            findings.push(Finding::NewerRelease {
+                tag: latest.tag_name.clone(),
+            });
+        }
+    }
+    findings
+}
+
+/// The highest semver tag among stable releases.
+pub fn latest_release<'a>(releases: &[&'a Release]) -> Option<&'a Release> {
+    releases
+        .iter()
+        .filter(|r| !r.prerelease && Version::new(&r.tag_name).parse().is_ok())
+        .max_by(|a, b| {
+            Version::new(&a.tag_name)
+                .compare(&Version::new(&b.tag_name))
+                .unwrap_or(Ordering::Equal)
+        })
+        .copied()
+}
+
+/// Checks every project with a package against its repository's releases.
+/// Each repository is only queried once.
+pub async fn check_groups(
+    client: &GithubClient,
+    groups: &HashMap<String, GroupData>,
+) -> Vec<ProjectCheck> {
+    let mut releases: HashMap<(String, String), ReleaseList> = HashMap::new();
+    let mut checks = Vec::new();
+
+    let mut names: Vec<&String> = groups.keys().collect();
+    names.sort();
+    for name in names {
+        for project in &groups[name].projects {
+            let Some(package) = &project.package else {
+                continue;
+            };
+            let key = (package.owner.clone(), package.repoName.clone());
+            if !releases.contains_key(&key) {
+                let result = client.releases(&package.owner, &package.repoName).await;
+                releases.insert(key.clone(), result);
+            }
+            checks.push(ProjectCheck {
+                group: name.clone(),
+                project: project.name.clone(),
+                package: package.clone(),
+                findings: check_package(package, &project.version, &releases[&key]),
+            });
+        }
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{route, MockServer};
+    use crate::types::Project;
+
+    fn project(
+        name: &str,
+        version: &str,
+        repo: &str,
+        package_version: &str,
+        file: &str,
+    ) -> Project {
+        let mut project = Project::new(name.to_string());
+        project.version = Version::new(version);
+        project.package = Some(Package {
+            owner: "owner".to_string(),
+            repoName: repo.to_string(),
+            version: Version::new(package_version),
+            fileName: file.to_string(),
+        });
+        project
+    }
+
+    fn release(tag: &str, asset: &str) -> serde_json::Value {
+        serde_json::json!({
+            "tag_name": tag,
+            "name": tag,
+            "published_at": "2024-05-01T12:00:00Z",
+            "assets": [{ "name": asset, "size": 1 }],
+        })
+    }
+
+    #[test]
+    fn candidate_tags_with_and_without_prefix() {
+        let tags = vec!["v1.2.0".to_string(), "1.2.0".to_string()];
+        assert_eq!(candidate_tags(&Version::new("1.2.0")), tags);
+        assert_eq!(candidate_tags(&Version::new("v1.2.0")), tags);
+    }
+
+    #[tokio::test]
+    async fn check_groups_against_releases() {
+        let server = MockServer::start(vec![
+            route(
+                "GET",
+                "/repos/owner/bare/releases?per_page=100",
+                200,
+                serde_json::json!([release("1.0.0", "bare.zip")]),
+            ),
+            route(
+                "GET",
+                "/repos/owner/prefixed/releases?per_page=100",
+                200,
+                serde_json::json!([release("v1.2.0", "prefixed.zip")]),
+            ),
+        ]);
+        let mut group = GroupData::new("Group".to_string());
+        group.projects = vec![
+            project("Bare", "1.0.0", "bare", "1.0.0", "bare.zip"),
+            project("Prefixed", "1.2.0", "prefixed", "1.2.0", "prefixed.zip"),
+            project("Gone", "1.0.0", "gone", "1.0.0", "gone.zip"),
+            project("No asset", "1.2.0", "prefixed", "v1.2.0", "other.zip"),
+            project("Mismatch", "1.1.0", "bare", "1.0.0", "bare.zip"),
+        ];
+        let groups = HashMap::from([("group".to_string(), group)]);
+
+        let checks = check_groups(&GithubClient::new(&server.url), &groups).await;
+        let findings: Vec<(&str, String)> = checks
+            .iter()
+            .flat_map(|check| {
+                check
+                    .findings
+                    .iter()
+                    .map(|finding| (check.project.as_str(), finding.to_string()))
+            })
+            .collect();
+        assert_eq!(
+            findings,
+            [
+                ("Bare", "release 1.0.0 has the asset".to_string()),
+                ("Prefixed", "release v1.2.0 has the asset".to_string()),
+                ("Gone", "repository owner/gone not found".to_string()),
+                (
+                    "No asset",
+                    "release v1.2.0 has no asset other.zip (assets: prefixed.zip)".to_string()
+                ),
+                (
+                    "Mismatch",
+                    "package version 1.0.0 does not match project version 1.1.0".to_string()
+                ),
+                ("Mismatch", "release 1.0.0 has the asset".to_string()),
+            ]
+        );
+        // Each repository is only asked for once.
+        let requested: Vec<String> = server
+            .requests()
+            .iter()
+            .map(|request| format!("{} {}", request.method, request.path))
+            .collect();
+        assert_eq!(
+            requested,
+            [
+                "GET /repos/owner/bare/releases?per_page=100",
+                "GET /repos/owner/prefixed/releases?per_page=100",
+                "GET /repos/owner/gone/releases?per_page=100",
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use chrono::DateTime;
+use eframe::egui::{self, Color32, ComboBox, Window};
+
+use crate::date::ProjectDate;
+use crate::github::{GithubClient, Release};
+use crate::release_check::latest_release;
+use crate::task::{self, SharedTask, Task};
+use crate::types::Project;
+use crate::version::Version;
+use crate::widgets::Confirm;
+
+type Releases = Result<Option<Vec<Release>>, String>;
+
+/// State of the "Sync from latest release" window for one project.
+#[derive(Debug, Clone)]
+pub struct SyncWizard {
+    pub group: String,
+    pub project: usize,
+    releases: SharedTask<Releases>,
+    release: usize,
+    asset: usize,
+    set_project_version: bool,
+    set_project_date: bool,
+}
+
+impl SyncWizard {
+    /// Opens the window and starts fetching the package's releases.
+    pub fn new(
+        ctx: &egui::Context,
+        client: GithubClient,
+        group: &str,
+        index: usize,
+        project: &Project,
+    ) -> Self {
+        let releases = SharedTask::default();
+        if let Some(package) = &project.package {
+            let owner = package.owner.clone();
+            let repo = package.repoName.clone();
+            task::spawn(ctx, &releases, async move {
+                client.releases(&owner, &repo).await
+            });
+        }
+        Self {
+            group: group.to_string(),
+            project: index,
+            releases,
+            release: 0,
+            asset: 0,
+            set_project_version: false,
+            set_project_date: false,
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context, project: &Project) -> Confirm {
+        let mut action = Confirm::None;
+        let mut open = true;
+        let state = self.releases.lock().unwrap().clone();
+        Window::new(format!("Sync {} from a release", project.name))
+            .id(egui::Id::new("release_sync"))
+            .open(&mut open)
+            .default_width(450.0)
+            .show(ctx, |ui| {
+                let Some(package) = &project.package else {
+                    ui.label("This project has no package");
+                    return;
+                };
+                ui.label(format!("{}/{}", package.owner, package.repoName));
+                let releases = match &state {
+                    Task::Idle | Task::Running => {
+                        ui.spinner();
+                        return;
+                    }
+                    Task::Done(Err(e)) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                        return;
+                    }
+                    Task::Done(Ok(None)) => {
+                        ui.colored_label(Color32::LIGHT_RED, "Repository not found");
+                        return;
+                    }
+                    Task::Done(Ok(Some(releases))) => releases,
+                };
+                let published: Vec<&Release> = releases.iter().filter(|r| !r.draft).collect();
+                if published.is_empty() {
+                    ui.label("No published releases");
+                    return;
+                }
+                if self.release >= published.len() {
+                    self.release = latest_release(&published)
+                        .and_then(|latest| {
+                            published.iter().position(|r| r.tag_name == latest.tag_name)
+                        })
+                        .unwrap_or(0);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Release");
+                    let before = self.release;
+                    ComboBox::from_id_source("sync_release")
+                        .selected_text(describe(published[self.release]))
+                        .show_ui(ui, |ui| {
+                            for (index, release) in published.iter().enumerate() {
+                                ui.selectable_value(&mut self.release, index, describe(release));
+                            }
+                        });
+                    if self.release != before {
+                        self.asset = 0;
+                    }
+                });
+                let release = published[self.release];
+                if release.assets.is_empty() {
+                    ui.colored_label(Color32::LIGHT_RED, "This release has no assets");
+                    return;
+                }
+                if self.asset >= release.assets.len() {
+                    self.asset = release
+                        .assets
+                        .iter()
+                        .position(|a| a.name == package.fileName)
+                        .unwrap_or(0);
+                }
+                ui.label("Asset");
+                for (index, asset) in release.assets.iter().enumerate() {
+                    ui.radio_value(
+                        &mut self.asset,
+                        index,
+                        format!("{} ({} KB)", asset.name, asset.size / 1024),
+                    );
+                }
+
+                ui.checkbox(
+                    &mut self.set_project_version,
+                    "Also set the project version",
+                );
+                ui.checkbox(
+                    &mut self.set_project_date,
+                    "Also set the project date to the release date",
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        action = Confirm::Apply;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Confirm::Cancel;
+                    }
+                });
+            });
+        if !open {
+            action = Confirm::Cancel;
+        }
+        action
+    }
+
+    /// Fills the package (and optionally project) fields from the chosen
+    /// release and asset.
+    pub fn apply(&self, project: &mut Project, date_format: &str) {
+        let Task::Done(Ok(Some(releases))) = &*self.releases.lock().unwrap() else {
+            return;
+        };
+        let published: Vec<&Release> = releases.iter().filter(|r| !r.draft).collect();
+        let Some(release) = published.get(self.release) else {
+            return;
+        };
+        let Some(package) = project.package.as_mut() else {
+            return;
+        };
+        let version = match_prefix(&release.tag_name, &package.version);
+        package.version = version.clone();
+        if let Some(asset) = release.assets.get(self.asset) {
+            package.fileName = asset.name.clone();
+        }
+        if self.set_project_version {
+            project.version = match_prefix(&release.tag_name, &project.version);
+        }
+        if self.set_project_date {
+            if let Some(date) = release
+                .published_at
+                .as_deref()
+                .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+            {
+                let mut project_date = ProjectDate::default();
+                project_date.set(date.date_naive(), date_format);
+                project.date = project_date;
+            }
+        }
+    }
+}
+
+fn describe(release: &Release) -> String {
+    let mut text = release.tag_name.clone();
+    if let Some(name) = release
+        .name
+        .as_deref()
+        .filter(|n| !n.is_empty() && *n != text)
+    {
+        text.push_str(&format!(" \"{}\"", name));
+    }
+    if let Some(date) = release.published_at.as_deref() {
+        text.push_str(&format!(" ({})", date.get(..10).unwrap_or(date)));
+    }
+    if release.prerelease {
+        text.push_str(" [pre-release]");
+    }
+    text
+}
+
+/// Writes `tag` with or without a leading `v` to match how `current` is
+/// written, so a sync doesn't change the file's version style.
+fn match_prefix(tag: &str, current: &Version) -> Version {
+    let bare = tag.trim().trim_start_matches(['v', 'V']);
+    if current.to_string().starts_with(['v', 'V']) {
+        Version::new(&format!("v{}", bare))
+    } else {
+        Version::new(bare)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::Asset;
+    use crate::types::Package;
+    use std::sync::{Arc, Mutex};
+
+    fn release(tag: &str, draft: bool, assets: &[&str]) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            name: None,
+            published_at: Some("2024-05-01T23:30:00-02:00".to_string()),
+            draft,
+            prerelease: false,
+            assets: assets
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    size: 1,
+                })
+                .collect(),
+        }
+    }
+
+    fn wizard(set_project: bool) -> SyncWizard {
+        let releases = vec![
+            release("v2.0.0", true, &["draft.zip"]),
+            release("v1.2.0", false, &["a.zip", "b.zip"]),
+        ];
+        SyncWizard {
+            group: "group".to_string(),
+            project: 0,
+            releases: Arc::new(Mutex::new(Task::Done(Ok(Some(releases))))),
+            release: 0,
+            asset: 1,
+            set_project_version: set_project,
+            set_project_date: set_project,
+        }
+    }
+
+    fn project() -> Project {
+        let mut project = Project::new("Project".to_string());
+        project.version = Version::new("1.0.0");
+        project.date.set(
+            chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            crate::date::ISO_8601,
+        );
+        project.package = Some(Package {
+            owner: "owner".to_string(),
+            repoName: "repo".to_string(),
+            version: Version::new("v1.0.0"),
+            fileName: "a.zip".to_string(),
+        });
+        project
+    }
+
+    #[test]
+    fn match_prefix_follows_current_version() {
+        let matched = |tag, current| match_prefix(tag, &Version::new(current)).to_string();
+        assert_eq!(matched("v1.2.0", "v1.0.0"), "v1.2.0");
+        assert_eq!(matched("v1.2.0", "1.0.0"), "1.2.0");
+        assert_eq!(matched("1.2.0", "V1.0.0"), "v1.2.0");
+        assert_eq!(matched("1.2.0", ""), "1.2.0");
+    }
+
+    #[test]
+    fn apply_sets_package_only() {
+        let mut project = project();
+        wizard(false).apply(&mut project, crate::date::ISO_8601);
+        let package = project.package.as_ref().unwrap();
+        assert_eq!(package.version.to_string(), "v1.2.0");
+        assert_eq!(package.fileName, "b.zip");
+        assert_eq!(project.version.to_string(), "1.0.0");
+        assert_eq!(project.date.to_string(), "2024-01-01");
+    }
+
+    #[test]
+    fn apply_sets_project_version_and_date() {
+        let mut project = project();
+        wizard(true).apply(&mut project, crate::date::ISO_8601);
+        assert_eq!(
+            project.package.as_ref().unwrap().version.to_string(),
+            "v1.2.0"
+        );
+        assert_eq!(project.version.to_string(), "1.2.0");
+        // The date the release was published on where it was published.
+        assert_eq!(project.date.to_string(), "2024-05-01");
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::HashMap;
+
+use eframe::egui::{self, Color32, Key, KeyboardShortcut, Modifiers, TextEdit};
+use regex::{NoExpand, Regex, RegexBuilder};
+
+use crate::types::GroupData;
+
+/// Longest value shown in the preview before it is cut short.
+const PREVIEW_CHARS: usize = 120;
+
+/// The kinds of field a replacement can be limited to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scope {
+    /// Logos and backgrounds.
+    pub urls: bool,
+    pub package_owners: bool,
+    /// Project overviews and descriptions.
+    pub descriptions: bool,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self {
+            urls: true,
+            package_owners: false,
+            descriptions: false,
+        }
+    }
+}
+
+impl Scope {
+    fn any(self) -> bool {
+        self.urls || self.package_owners || self.descriptions
+    }
+}
+
+/// A field in scope, with the project it belongs to if any.
+type ScopedField<'a, T> = (Option<(usize, &'a str)>, &'static str, T);
+
+fn fields(group: &GroupData, scope: Scope) -> Vec<ScopedField<'_, &String>> {
+    let mut fields = Vec::new();
+    if scope.urls {
+        fields.push((None, "logo", &group.logo));
+        fields.push((None, "beta background", &group.beta.background));
+    }
+    for (index, project) in group.projects.iter().enumerate() {
+        let at = Some((index, project.name.as_str()));
+        if scope.urls {
+            fields.push((at, "background", &project.background));
+            if let Some(url) = &project.pageBackground {
+                fields.push((at, "page background", url));
+            }
+        }
+        if scope.package_owners {
+            if let Some(package) = &project.package {
+                fields.push((at, "package owner", &package.owner));
+            }
+        }
+        if scope.descriptions {
+            fields.push((at, "overview", &project.overview));
+            fields.push((at, "description", &project.description));
+        }
+    }
+    fields
+}
+
+/// The same fields as `fields`, for writing. Project names are not needed
+/// here so they are left out to keep the borrows apart.
+fn fields_mut(group: &mut GroupData, scope: Scope) -> Vec<&mut String> {
+    let mut fields = Vec::new();
+    if scope.urls {
+        fields.push(&mut group.logo);
+        fields.push(&mut group.beta.background);
+    }
+    for project in &mut group.projects {
+        if scope.urls {
+            fields.push(&mut project.background);
+            if let Some(url) = &mut project.pageBackground {
+                fields.push(url);
+            }
+        }
+        if scope.package_owners {
+            if let Some(package) = &mut project.package {
+                fields.push(&mut package.owner);
+            }
+        }
+        if scope.descriptions {
+            fields.push(&mut project.overview);
+            fields.push(&mut project.description);
+        }
+    }
+    fields
+}
+
+/// A find and replace to run over the groups.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replacement {
+    pub find: String,
+    pub replace: String,
+    /// Treat `find` as a regular expression; `replace` can then use `$1`.
+    pub regex: bool,
+    pub match_case: bool,
+    pub scope: Scope,
+}
+
+/// A field the replacement changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub group: String,
+    pub project: Option<String>,
+    pub field: &'static str,
+    pub matches: usize,
+    pub before: String,
+    pub after: String,
+}
+
+impl Replacement {
+    fn pattern(&self) -> Result<Regex, String> {
+        let pattern = if self.regex {
+            self.find.clone()
+        } else {
+            regex::escape(&self.find)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.match_case)
+            .build()
+            .map_err(|e| format!("Invalid regular expression: {}", e))
+    }
+
+    fn replace_in(&self, pattern: &Regex, text: &str) -> String {
+        if self.regex {
+            pattern
+                .replace_all(text, self.replace.as_str())
+                .into_owned()
+        } else {
+            pattern
+                .replace_all(text, NoExpand(&self.replace))
+                .into_owned()
+        }
+    }
+
+    /// Every field the replacement would change, in group and project
+    /// order, without changing anything.
+    pub fn preview(&self, groups: &HashMap<String, GroupData>) -> Result<Vec<Change>, String> {
+        if self.find.is_empty() {
+            return Ok(Vec::new());
+        }
+        let pattern = self.pattern()?;
+        let mut names: Vec<&String> = groups.keys().collect();
+        names.sort();
+        let mut changes = Vec::new();
+        for name in names {
+            for (project, field, text) in fields(&groups[name], self.scope) {
+                let matches = pattern.find_iter(text).count();
+                if matches == 0 {
+                    continue;
+                }
+                let after = self.replace_in(&pattern, text);
+                if after == *text {
+                    continue;
+                }
+                changes.push(Change {
+                    group: name.clone(),
+                    project: project.map(|(_, name)| name.to_string()),
+                    field,
+                    matches,
+                    before: text.clone(),
+                    after,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Replaces every match and returns how many fields changed.
+    pub fn apply(&self, groups: &mut HashMap<String, GroupData>) -> Result<usize, String> {
+        if self.find.is_empty() {
+            return Ok(0);
+        }
+        let pattern = self.pattern()?;
+        let mut changed = 0;
+        for group in groups.values_mut() {
+            for text in fields_mut(group, self.scope) {
+                let after = self.replace_in(&pattern, text);
+                if after != *text {
+                    *text = after;
+                    changed += 1;
+                }
+            }
+        }
+        Ok(changed)
+    }
+}
+
+fn shorten(text: &str) -> String {
+    let text = text.replace('\n', " ⏎ ");
+    if text.chars().count() > PREVIEW_CHARS {
+        let cut: String = text.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        text
+    }
+}
+
+/// The changes a replacement makes, kept with the replacement and groups
+/// they were worked out for so they are only worked out again when one of
+/// them changes.
+#[derive(Debug, Clone)]
+struct Preview {
+    replacement: Replacement,
+    groups: HashMap<String, GroupData>,
+    changes: Result<Vec<Change>, String>,
+}
+
+/// The Ctrl+H find and replace window.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceWindow {
+    open: bool,
+    focus: bool,
+    replacement: Replacement,
+    preview: Option<Preview>,
+    /// How the last "Replace all" went, until the replacement is changed.
+    applied: Option<(Replacement, Result<usize, String>)>,
+}
+
+impl ReplaceWindow {
+    pub fn show(&mut self) {
+        self.open = true;
+        self.focus = true;
+    }
+
+    /// Shows what applying the replacement `ui` returned did.
+    pub fn applied(&mut self, replacement: Replacement, result: Result<usize, String>) {
+        self.applied = Some((replacement, result));
+    }
+
+    /// Returns the replacement to apply once "Replace all" is clicked.
+    pub fn ui(
+        &mut self,
+        ctx: &egui::Context,
+        groups: &HashMap<String, GroupData>,
+    ) -> Option<Replacement> {
+        if ctx.input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::H)))
+        {
+            self.show();
+        }
+        if !self.open {
+            self.preview = None;
+            return None;
+        }
+        let mut apply = None;
+        let mut open = self.open;
+        egui::Window::new("Find and replace")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                let replacement = &mut self.replacement;
+                egui::Grid::new("replace_fields")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Find");
+                        let find = ui.add(
+                            TextEdit::singleline(&mut replacement.find)
+                                .desired_width(f32::INFINITY),
+                        );
+                        if std::mem::take(&mut self.focus) {
+                            find.request_focus();
+                        }
+                        ui.end_row();
+                        ui.label("Replace with");
+                        ui.add(
+                            TextEdit::singleline(&mut replacement.replace)
+                                .desired_width(f32::INFINITY),
+                        );
+                        ui.end_row();
+                    });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut replacement.regex, "Regular expression")
+                        .on_hover_text("Use $1, $2... or ${name} in the replacement for groups");
+                    ui.checkbox(&mut replacement.match_case, "Match case");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("In:");
+                    ui.checkbox(&mut replacement.scope.urls, "URLs");
+                    ui.checkbox(&mut replacement.scope.package_owners, "Package owners");
+                    ui.checkbox(&mut replacement.scope.descriptions, "Descriptions");
+                });
+                ui.separator();
+
+                if self
+                    .applied
+                    .as_ref()
+                    .is_some_and(|(applied, _)| applied != replacement)
+                {
+                    self.applied = None;
+                }
+                match &self.applied {
+                    Some((_, Ok(changed))) => {
+                        ui.label(format!("Replaced in {} fields", changed));
+                    }
+                    Some((_, Err(e))) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                    }
+                    None => {}
+                }
+                if replacement.find.is_empty() || !replacement.scope.any() {
+                    ui.label("Enter something to find and pick the fields to look in");
+                    return;
+                }
+                let current = self.preview.as_ref().is_some_and(|preview| {
+                    preview.replacement == *replacement && preview.groups == *groups
+                });
+                if !current {
+                    self.preview = Some(Preview {
+                        replacement: replacement.clone(),
+                        groups: groups.clone(),
+                        changes: replacement.preview(groups),
+                    });
+                }
+                let Some(preview) = &self.preview else {
+                    return;
+                };
+                let changes = match &preview.changes {
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        ui.colored_label(Color32::LIGHT_RED, e);
+                        return;
+                    }
+                };
+                let matches: usize = changes.iter().map(|change| change.matches).sum();
+                ui.label(format!("{} matches in {} fields", matches, changes.len()));
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("replace_preview")
+                            .striped(true)
+                            .num_columns(3)
+                            .show(ui, |ui| {
+                                for change in changes {
+                                    let place = match &change.project {
+                                        Some(project) => {
+                                            format!("{} / {}", change.group, project)
+                                        }
+                                        None => change.group.clone(),
+                                    };
+                                    ui.label(format!("{} · {}", place, change.field));
+                                    ui.colored_label(Color32::LIGHT_RED, shorten(&change.before))
+                                        .on_hover_text(&change.before);
+                                    ui.colored_label(Color32::LIGHT_GREEN, shorten(&change.after))
+                                        .on_hover_text(&change.after);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                if ui
+                    .add_enabled(!changes.is_empty(), egui::Button::new("Replace all"))
+                    .clicked()
+                {
+                    apply = Some(replacement.clone());
+                }
+            });
+        self.open = open;
+        apply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Package, Project};
+
+    fn sample() -> HashMap<String, GroupData> {
+        let mut a = GroupData::new("Old group".to_string());
+        a.logo = "https://old.example/a.png".to_string();
+        let mut project = Project::new("Old project".to_string());
+        project.background = "https://old.example/p.png".to_string();
+        project.overview = "Old news, old.example".to_string();
+        project.package = Some(Package {
+            owner: "OldOwner".to_string(),
+            ..Package::default()
+        });
+        a.projects.push(project);
+        let mut b = GroupData::new("B".to_string());
+        b.logo = "https://OLD.example/b.png".to_string();
+        HashMap::from([("a".to_string(), a), ("b".to_string(), b)])
+    }
+
+    fn replacement(find: &str, replace: &str, regex: bool, match_case: bool) -> Replacement {
+        Replacement {
+            find: find.to_string(),
+            replace: replace.to_string(),
+            regex,
+            match_case,
+            scope: Scope::default(),
+        }
+    }
+
+    /// Runs `preview` and `apply` and checks they agree, returning the
+    /// previewed changes and the groups after applying.
+    fn run(replacement: &Replacement) -> (Vec<Change>, HashMap<String, GroupData>) {
+        let mut groups = sample();
+        let changes = replacement.preview(&groups).unwrap();
+        assert_eq!(replacement.apply(&mut groups), Ok(changes.len()));
+        (changes, groups)
+    }
+
+    #[test]
+    fn literal_replacements_do_not_expand() {
+        let (changes, groups) = run(&replacement("old.example", "$1new.example", false, false));
+        let fields: Vec<_> = changes
+            .iter()
+            .map(|c| (c.group.as_str(), c.project.as_deref(), c.field))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("a", None, "logo"),
+                ("a", Some("Old project"), "background"),
+                ("b", None, "logo"),
+            ]
+        );
+        assert_eq!(changes[0].after, "https://$1new.example/a.png");
+        assert_eq!(groups["a"].logo, "https://$1new.example/a.png");
+        assert_eq!(groups["b"].logo, "https://$1new.example/b.png");
+        // The dot is literal.
+        assert!(run(&replacement("old_example", "x", false, false))
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn match_case() {
+        let (changes, groups) = run(&replacement("old.example", "new.example", false, true));
+        assert_eq!(changes.len(), 2);
+        assert_eq!(groups["b"].logo, "https://OLD.example/b.png");
+    }
+
+    #[test]
+    fn regex_replacements_expand_groups() {
+        let (changes, groups) = run(&replacement(
+            r"https://(\w+)\.example",
+            "https://cdn.$1.example",
+            true,
+            false,
+        ));
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].matches, 1);
+        assert_eq!(groups["a"].logo, "https://cdn.old.example/a.png");
+        assert_eq!(groups["b"].logo, "https://cdn.OLD.example/b.png");
+
+        let (changes, _) = run(&replacement("[aeiou]", "", true, true));
+        assert_eq!(changes[0].field, "logo");
+        assert_eq!(changes[0].matches, 5);
+        assert_eq!(changes[0].after, "https://ld.xmpl/.png");
+
+        let mut groups = sample();
+        let invalid = replacement("(", "", true, false);
+        assert!(invalid.preview(&groups).is_err());
+        assert!(invalid.apply(&mut groups).is_err());
+        assert_eq!(replacement("", "x", true, false).apply(&mut groups), Ok(0));
+    }
+
+    #[test]
+    fn fields_out_of_scope_are_untouched() {
+        let mut descriptions = replacement("old", "new", false, false);
+        descriptions.scope = Scope {
+            urls: false,
+            package_owners: false,
+            descriptions: true,
+        };
+        let (changes, groups) = run(&descriptions);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].matches, 2);
+        let project = &groups["a"].projects[0];
+        assert_eq!(project.overview, "new news, new.example");
+        assert_eq!(project.name, "Old project");
+        assert_eq!(groups["a"].name, "Old group");
+        assert_eq!(groups["a"].logo, "https://old.example/a.png");
+        assert_eq!(project.package.as_ref().unwrap().owner, "OldOwner");
+
+        let mut owners = descriptions.clone();
+        owners.scope = Scope {
+            urls: false,
+            package_owners: true,
+            descriptions: false,
+        };
+        let (_, groups) = run(&owners);
+        let project = &groups["a"].projects[0];
+        assert_eq!(project.package.as_ref().unwrap().owner, "newOwner");
+        assert_eq!(project.overview, "Old news, old.example");
+    }
+}
@@ -0,0 +1,91 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{InstanceType, RootSchema, Schema, SchemaObject, StringValidation};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+
+use crate::date;
+use crate::types::GroupData;
+
+pub const HEX_COLOUR: &str = "^#[0-9a-fA-F]{6}$";
+
+/// `schema` or `""`, for fields that `GroupData::new` and `Project::new`
+/// leave empty until they are filled in.
+pub fn or_empty(schema: SchemaObject, nullable: bool) -> Schema {
+    let empty = SchemaObject {
+        const_value: Some("".into()),
+        ..Default::default()
+    };
+    let mut any_of = vec![schema.into(), empty.into()];
+    if nullable {
+        let null = SchemaObject {
+            instance_type: Some(InstanceType::Null.into()),
+            ..Default::default()
+        };
+        any_of.push(null.into());
+    }
+    let mut object = SchemaObject::default();
+    object.subschemas().any_of = Some(any_of);
+    object.into()
+}
+
+fn url() -> SchemaObject {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        format: Some("uri".to_string()),
+        ..Default::default()
+    }
+}
+
+pub fn url_or_empty(_: &mut SchemaGenerator) -> Schema {
+    or_empty(url(), false)
+}
+
+pub fn optional_url_or_empty(_: &mut SchemaGenerator) -> Schema {
+    or_empty(url(), true)
+}
+
+pub fn colour_or_empty(_: &mut SchemaGenerator) -> Schema {
+    let colour = SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        string: Some(Box::new(StringValidation {
+            pattern: Some(HEX_COLOUR.to_string()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    or_empty(colour, false)
+}
+
+pub const SCHEMA_FILE: &str = "groups.schema.json";
+
+/// The schema for groups.json with project dates written in `date_format`.
+pub fn groups_schema(date_format: &str) -> RootSchema {
+    let mut gen = SchemaGenerator::new(SchemaSettings::draft07());
+    let mut root = gen.root_schema_for::<HashMap<String, GroupData>>();
+    root.schema.metadata().title = Some("groups.json".to_string());
+    if let Some(Schema::Object(project)) = root.definitions.get_mut("Project") {
+        if let Some(Schema::Object(date)) = project.object().properties.get_mut("date") {
+            // The date itself is the first branch; the other is `""`.
+            if let Some(Schema::Object(date)) = date
+                .subschemas()
+                .any_of
+                .as_mut()
+                .and_then(|any_of| any_of.first_mut())
+            {
+                date.format = Some(date::schema_format(date_format));
+            }
+        }
+    }
+    root
+}
+
+pub fn groups_schema_json(date_format: &str) -> String {
+    serde_json::to_string_pretty(&groups_schema(date_format)).unwrap()
+}
+
+pub fn write_schema(path: &str, date_format: &str) -> Result<(), String> {
+    let mut file = File::create(path).map_err(|e| format!("Error creating {}: {}", path, e))?;
+    file.write_all(groups_schema_json(date_format).as_bytes())
+        .map_err(|e| format!("Error writing {}: {}", path, e))
+}
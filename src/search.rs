@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use eframe::egui::{
+    self, Align, Color32, Key, KeyboardShortcut, Modifiers, Rect, Stroke, TextEdit,
+};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+use crate::types::{GroupData, Project};
+
+/// Most results listed, best first.
+const MAX_RESULTS: usize = 200;
+/// How long a field found by a search stays outlined, in seconds.
+const REVEAL_SECONDS: f64 = 2.0;
+
+/// A searchable field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    GroupName,
+    ProjectName,
+    Version,
+    Changelog,
+    Overview,
+    Description,
+    PackageOwner,
+    PackageRepo,
+    PackageVersion,
+    PackageFile,
+}
+
+impl Field {
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::GroupName => "group name",
+            Field::ProjectName => "project name",
+            Field::Version => "version",
+            Field::Changelog => "changelog",
+            Field::Overview => "overview",
+            Field::Description => "description",
+            Field::PackageOwner => "package owner",
+            Field::PackageRepo => "package repository",
+            Field::PackageVersion => "package version",
+            Field::PackageFile => "package file",
+        }
+    }
+}
+
+/// Narrow a search down. Each one that is ticked has to hold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Filters {
+    pub hidden_groups: bool,
+    pub has_package: bool,
+    pub missing_date: bool,
+}
+
+impl Filters {
+    fn any(self) -> bool {
+        self.hidden_groups || self.has_package || self.missing_date
+    }
+
+    fn group(self, group: &GroupData) -> bool {
+        !self.hidden_groups || group.hide == Some(true)
+    }
+
+    /// Whether group level fields can match; the project filters rule
+    /// them out.
+    fn group_fields(self) -> bool {
+        !self.has_package && !self.missing_date
+    }
+
+    fn project(self, project: &Project) -> bool {
+        (!self.has_package || project.package.is_some())
+            && (!self.missing_date || project.date.parse().is_err())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub group: String,
+    /// Index and name of the project, `None` for group fields.
+    pub project: Option<(usize, String)>,
+    pub field: Field,
+    /// The matching line.
+    pub snippet: String,
+    pub score: i64,
+}
+
+/// The best matching line of `text`, scored. Long texts are matched line by
+/// line so a query does not match letters scattered across a whole
+/// changelog.
+fn best_line(matcher: &SkimMatcherV2, text: &str, query: &str) -> Option<(i64, String)> {
+    text.lines()
+        .filter_map(|line| {
+            matcher
+                .fuzzy_match(line, query)
+                .map(|score| (score, line.trim()))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(score, line)| {
+            let snippet: String = line.chars().take(80).collect();
+            (score, snippet)
+        })
+}
+
+fn project_fields(project: &Project) -> Vec<(Field, String)> {
+    let mut fields = vec![
+        (Field::ProjectName, project.name.clone()),
+        (Field::Version, project.version.to_string()),
+        (Field::Changelog, project.changelog.clone()),
+        (Field::Overview, project.overview.clone()),
+        (Field::Description, project.description.clone()),
+    ];
+    if let Some(package) = &project.package {
+        fields.extend([
+            (Field::PackageOwner, package.owner.clone()),
+            (Field::PackageRepo, package.repoName.clone()),
+            (Field::PackageVersion, package.version.to_string()),
+            (Field::PackageFile, package.fileName.clone()),
+        ]);
+    }
+    fields
+}
+
+/// Fuzzy matches `query` against every searchable field. With an empty
+/// query every group or project that passes the filters is listed.
+pub fn search(groups: &HashMap<String, GroupData>, query: &str, filters: Filters) -> Vec<Hit> {
+    let query = query.trim();
+    if query.is_empty() && !filters.any() {
+        return Vec::new();
+    }
+    let matcher = SkimMatcherV2::default().smart_case();
+    let mut hits = Vec::new();
+    let mut names: Vec<&String> = groups.keys().collect();
+    names.sort();
+    for name in names {
+        let group = &groups[name];
+        if !filters.group(group) {
+            continue;
+        }
+        if filters.group_fields() {
+            let hit = if query.is_empty() {
+                Some((0, group.name.clone()))
+            } else {
+                best_line(&matcher, &group.name, query)
+            };
+            if let Some((score, snippet)) = hit {
+                hits.push(Hit {
+                    group: name.clone(),
+                    project: None,
+                    field: Field::GroupName,
+                    snippet,
+                    score,
+                });
+            }
+        }
+        for (index, project) in group.projects.iter().enumerate() {
+            if !filters.project(project) {
+                continue;
+            }
+            let found: Vec<(Field, i64, String)> = if query.is_empty() {
+                vec![(Field::ProjectName, 0, project.name.clone())]
+            } else {
+                project_fields(project)
+                    .into_iter()
+                    .filter_map(|(field, text)| {
+                        best_line(&matcher, &text, query).map(|(score, line)| (field, score, line))
+                    })
+                    .collect()
+            };
+            hits.extend(found.into_iter().map(|(field, score, snippet)| Hit {
+                group: name.clone(),
+                project: Some((index, project.name.clone())),
+                field,
+                snippet,
+                score,
+            }));
+        }
+    }
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.score));
+    hits.truncate(MAX_RESULTS);
+    hits
+}
+
+/// A field to bring into view after picking a search result.
+#[derive(Debug, Clone)]
+pub struct Reveal {
+    pub field: Field,
+    started: Option<f64>,
+}
+
+impl Reveal {
+    pub fn new(field: Field) -> Self {
+        Self {
+            field,
+            started: None,
+        }
+    }
+}
+
+/// Call after drawing `field` at `rect`. If it is the field being revealed
+/// it is scrolled to the first time and outlined for a moment.
+pub fn reveal(reveal: &mut Option<Reveal>, ui: &egui::Ui, rect: Rect, field: Field) {
+    let Some(state) = reveal.as_mut().filter(|state| state.field == field) else {
+        return;
+    };
+    let now = ui.input(|i| i.time);
+    let started = *state.started.get_or_insert_with(|| {
+        ui.scroll_to_rect(rect, Some(Align::Center));
+        now
+    });
+    if now - started > REVEAL_SECONDS {
+        *reveal = None;
+        return;
+    }
+    ui.painter()
+        .rect_stroke(rect.expand(2.0), 2.0, Stroke::new(2.0, Color32::GOLD));
+    ui.ctx().request_repaint();
+}
+
+/// The Ctrl+F search window.
+#[derive(Debug, Clone, Default)]
+pub struct SearchPanel {
+    open: bool,
+    focus: bool,
+    query: String,
+    filters: Filters,
+}
+
+impl SearchPanel {
+    /// Returns the result that was clicked.
+    pub fn ui(&mut self, ctx: &egui::Context, groups: &HashMap<String, GroupData>) -> Option<Hit> {
+        if ctx.input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::F)))
+        {
+            self.open = true;
+            self.focus = true;
+        }
+        let mut picked = None;
+        let mut open = self.open;
+        egui::Window::new("Search")
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                let field = ui.add(
+                    TextEdit::singleline(&mut self.query)
+                        .hint_text("Group, project, version, changelog, package...")
+                        .desired_width(f32::INFINITY),
+                );
+                if std::mem::take(&mut self.focus) {
+                    field.request_focus();
+                }
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.filters.hidden_groups, "Hidden groups");
+                    ui.checkbox(&mut self.filters.has_package, "Has package");
+                    ui.checkbox(&mut self.filters.missing_date, "Missing date");
+                });
+                ui.separator();
+
+                let hits = search(groups, &self.query, self.filters);
+                if hits.is_empty() && (!self.query.trim().is_empty() || self.filters.any()) {
+                    ui.label("No matches");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for hit in hits {
+                            let place = match &hit.project {
+                                Some((_, project)) => format!("{} / {}", hit.group, project),
+                                None => hit.group.clone(),
+                            };
+                            let text =
+                                format!("{} · {}: {}", place, hit.field.label(), hit.snippet);
+                            if ui.selectable_label(false, text).clicked() {
+                                picked = Some(hit);
+                            }
+                        }
+                    });
+            });
+        self.open = open;
+        picked
+    }
+}
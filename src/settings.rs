@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+
+use crate::date;
+use crate::github;
+use crate::image_check::ImageRules;
+
+pub const SETTINGS_FILE: &str = "group-manager.settings.json";
+
+/// User preferences, kept next to the generated groups.json.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// chrono format string that project dates are written in.
+    pub date_format: String,
+    /// Base URL of the GitHub REST API.
+    pub github_api_url: String,
+    /// Where the OAuth device flow is run, normally https://github.com.
+    pub github_login_url: String,
+    /// Client ID of the OAuth app used for the device flow.
+    pub github_client_id: String,
+    /// `owner/name` of the repository groups.json is submitted to.
+    pub groups_repo: String,
+    /// Branch pull requests are opened against; empty for the default.
+    pub groups_branch: String,
+    /// Path of groups.json inside the repository.
+    pub groups_path: String,
+    /// Local clone of the groups repository, empty if there is none.
+    pub checkout_path: String,
+    /// Remote of the local clone that is pulled from and pushed to.
+    pub git_remote: String,
+    /// Expected shape and size of the images in each kind of field.
+    pub image_rules: ImageRules,
+    /// Local clone of the repository images are hosted in, empty if none.
+    pub assets_checkout: String,
+    /// URL the files in the assets checkout are served from.
+    pub assets_url: String,
+    /// Where imported images go in the assets checkout, using `{group}`,
+    /// `{project}` and `{field}`.
+    pub assets_path: String,
+    /// WebP quality (0-100) imported images are encoded at.
+    pub webp_quality: f32,
+    /// Most space remote image thumbnails may take up on disk, in MB.
+    pub thumbnail_cache_mb: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            date_format: date::ISO_8601.to_string(),
+            github_api_url: github::DEFAULT_API_URL.to_string(),
+            github_login_url: github::DEFAULT_LOGIN_URL.to_string(),
+            github_client_id: String::new(),
+            groups_repo: "infinity-MSFS/groups".to_string(),
+            groups_branch: "main".to_string(),
+            groups_path: "groups.json".to_string(),
+            checkout_path: String::new(),
+            git_remote: "origin".to_string(),
+            image_rules: ImageRules::default(),
+            assets_checkout: String::new(),
+            assets_url: String::new(),
+            assets_path: "{group}/{project}/{field}.webp".to_string(),
+            webp_quality: 80.0,
+            thumbnail_cache_mb: 100,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        std::fs::read_to_string(SETTINGS_FILE)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(self).unwrap();
+        let mut file = File::create(SETTINGS_FILE)
+            .map_err(|e| format!("Error creating {}: {}", SETTINGS_FILE, e))?;
+        file.write_all(&json)
+            .map_err(|e| format!("Error writing {}: {}", SETTINGS_FILE, e))
+    }
+
+    /// The configured date format, or ISO-8601 if it isn't usable.
+    pub fn date_format(&self) -> &str {
+        if date::is_valid_format(&self.date_format) {
+            &self.date_format
+        } else {
+            date::ISO_8601
+        }
+    }
+
+    /// The thumbnail cache limit in bytes. The settings file can hold any
+    /// number of MB, so this saturates rather than overflowing.
+    pub fn thumbnail_cache_bytes(&self) -> u64 {
+        self.thumbnail_cache_mb.saturating_mul(1024 * 1024)
+    }
+}
@@ -0,0 +1,420 @@
+use eframe::egui::{self, Color32, Window};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::date;
+use crate::diff::Diff;
+use crate::github::{GithubClient, PullRequest};
+use crate::settings::Settings;
+use crate::task::{self, SharedTask, Task};
+use crate::types::{self, GroupData};
+use crate::validate::{self, LoadReport};
+
+/// The file a submission replaces.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub owner: String,
+    pub repo: String,
+    /// Branch the pull request goes into; empty for the default branch.
+    pub base: String,
+    pub path: String,
+}
+
+impl Target {
+    pub fn from_settings(settings: &Settings) -> Result<Self, String> {
+        let Some((owner, repo)) = settings
+            .groups_repo
+            .trim()
+            .split_once('/')
+            .filter(|(owner, repo)| !owner.is_empty() && !repo.is_empty())
+        else {
+            return Err(format!(
+                "Groups repository \"{}\" is not in owner/name form",
+                settings.groups_repo
+            ));
+        };
+        Ok(Self {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            base: settings.groups_branch.trim().to_string(),
+            path: settings
+                .groups_path
+                .trim()
+                .trim_start_matches('/')
+                .to_string(),
+        })
+    }
+}
+
+/// Title and body for a pull request going from `before` to `after`.
+/// `unreadable` names the groups that failed to load from the repository:
+/// they are missing from `before`, so the body says the summary may list
+/// them as new.
+pub fn summarise(
+    before: &HashMap<String, GroupData>,
+    unreadable: &[String],
+    after: &HashMap<String, GroupData>,
+) -> (String, String) {
+    let diff = Diff::new(before, after);
+    let mut body = diff.to_markdown();
+    if !unreadable.is_empty() {
+        body.push_str(&format!("\n\n{}", unreadable_warning(unreadable)));
+    }
+    (diff.title(), body + "\n\n_Submitted with Group Manager._")
+}
+
+fn unreadable_warning(unreadable: &[String]) -> String {
+    format!(
+        "Groups that could not be read from the repository, and may be listed as new: {}",
+        unreadable.join(", ")
+    )
+}
+
+/// Commits `text` to a new branch and opens a pull request with it. The
+/// branch goes on a fork unless the signed in user owns the repository.
+pub async fn submit(
+    client: &GithubClient,
+    target: &Target,
+    branch: &str,
+    title: &str,
+    body: &str,
+    text: &str,
+) -> Result<PullRequest, String> {
+    let user = client
+        .user()
+        .await?
+        .ok_or_else(|| "Sign in to GitHub first".to_string())?;
+    let upstream = client
+        .repo(&target.owner, &target.repo)
+        .await?
+        .ok_or_else(|| format!("Repository {}/{} not found", target.owner, target.repo))?;
+    let base = if target.base.is_empty() {
+        upstream.default_branch.clone()
+    } else {
+        target.base.clone()
+    };
+    let base_sha = client
+        .branch_sha(&target.owner, &target.repo, &base)
+        .await?
+        .ok_or_else(|| format!("Branch {} not found", base))?;
+
+    let (head_owner, head_repo) = if user.login.eq_ignore_ascii_case(&target.owner) {
+        (upstream.owner.login, upstream.name)
+    } else {
+        let fork = client.create_fork(&target.owner, &target.repo).await?;
+        // Forking is asynchronous, wait until the fork answers.
+        let mut attempts = 0;
+        while client.repo(&fork.owner.login, &fork.name).await?.is_none() {
+            attempts += 1;
+            if attempts == 10 {
+                return Err(format!(
+                    "The fork {}/{} did not appear",
+                    fork.owner.login, fork.name
+                ));
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        (fork.owner.login, fork.name)
+    };
+
+    client
+        .create_branch(&head_owner, &head_repo, branch, &base_sha)
+        .await?;
+    let existing = client
+        .file(&head_owner, &head_repo, &target.path, branch)
+        .await?;
+    client
+        .put_file(
+            &head_owner,
+            &head_repo,
+            &target.path,
+            branch,
+            title,
+            text,
+            existing.as_ref().map(|file| file.sha.as_str()),
+        )
+        .await?;
+    client
+        .create_pull(
+            &target.owner,
+            &target.repo,
+            title,
+            body,
+            &format!("{}:{}", head_owner, branch),
+            &base,
+        )
+        .await
+}
+
+/// The groups currently in the target repository, to summarise against.
+/// Groups that fail to load are in the report's `broken`.
+pub async fn fetch_upstream(client: &GithubClient, target: &Target) -> Result<LoadReport, String> {
+    let base = if target.base.is_empty() {
+        client
+            .repo(&target.owner, &target.repo)
+            .await?
+            .map(|repo| repo.default_branch)
+            .unwrap_or_default()
+    } else {
+        target.base.clone()
+    };
+    let Some(file) = client
+        .file(&target.owner, &target.repo, &target.path, &base)
+        .await?
+    else {
+        return Ok(LoadReport::default());
+    };
+    validate::load_groups(&file.text()?, date::ISO_8601).map_err(|e| e.to_string())
+}
+
+/// A branch name that won't clash with earlier submissions.
+pub fn branch_name() -> String {
+    format!(
+        "groups-update-{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    )
+}
+
+/// The "Submit changes" window.
+#[derive(Debug, Clone)]
+pub struct SubmitWindow {
+    title: String,
+    body: String,
+    branch: String,
+    compared_to_upstream: bool,
+    /// Groups that failed to load from the repository.
+    unreadable: Vec<String>,
+    result: SharedTask<Result<PullRequest, String>>,
+}
+
+impl SubmitWindow {
+    pub fn new(
+        upstream: &HashMap<String, GroupData>,
+        unreadable: Vec<String>,
+        groups: &HashMap<String, GroupData>,
+    ) -> Self {
+        let (title, body) = summarise(upstream, &unreadable, groups);
+        Self {
+            title,
+            body,
+            branch: branch_name(),
+            compared_to_upstream: !upstream.is_empty(),
+            unreadable,
+            result: SharedTask::default(),
+        }
+    }
+
+    /// Returns false once the window has been closed.
+    pub fn ui(
+        &mut self,
+        ctx: &egui::Context,
+        client: impl FnOnce() -> GithubClient,
+        settings: &Settings,
+        groups: &HashMap<String, GroupData>,
+        broken: &HashMap<String, String>,
+    ) -> bool {
+        let mut open = true;
+        let state = self.result.lock().unwrap().clone();
+        Window::new("Submit changes")
+            .id(egui::Id::new("submit_changes"))
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                let target = Target::from_settings(settings);
+                match &target {
+                    Ok(target) => ui.label(format!(
+                        "Opens a pull request against {}/{} changing {}",
+                        target.owner, target.repo, target.path
+                    )),
+                    Err(e) => ui.colored_label(Color32::LIGHT_RED, e),
+                };
+                if !self.compared_to_upstream {
+                    ui.colored_label(
+                        Color32::YELLOW,
+                        "Fetch the group data from the repo to summarise only what changed",
+                    );
+                }
+                if !self.unreadable.is_empty() {
+                    ui.colored_label(Color32::YELLOW, unreadable_warning(&self.unreadable));
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Branch");
+                    ui.text_edit_singleline(&mut self.branch);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Title");
+                    ui.add(egui::TextEdit::singleline(&mut self.title).desired_width(400.0));
+                });
+                ui.label("Description");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.body)
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY),
+                );
+
+                match &state {
+                    Task::Running => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Submitting");
+                        });
+                    }
+                    Task::Done(Ok(pull)) => {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Opened pull request #{}", pull.number));
+                            ui.hyperlink(&pull.html_url);
+                        });
+                    }
+                    Task::Idle | Task::Done(Err(_)) => {
+                        if let Task::Done(Err(e)) = &state {
+                            ui.colored_label(Color32::LIGHT_RED, e);
+                        }
+                        let ready = target.is_ok()
+                            && !self.title.trim().is_empty()
+                            && !self.branch.trim().is_empty();
+                        if ui.add_enabled(ready, egui::Button::new("Submit")).clicked() {
+                            if let Ok(target) = target {
+                                let client = client();
+                                let branch = self.branch.trim().to_string();
+                                let title = self.title.trim().to_string();
+                                let body = self.body.clone();
+                                let text = types::document_json(groups, broken);
+                                task::spawn(ctx, &self.result, async move {
+                                    submit(&client, &target, &branch, &title, &body, &text).await
+                                });
+                            }
+                        }
+                    }
+                }
+            });
+        open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{route, MockServer};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn summarises_the_diff() {
+        let mut before = HashMap::from([("a".to_string(), GroupData::new("A".to_string()))]);
+        let after = HashMap::from([("b".to_string(), GroupData::new("B".to_string()))]);
+        let (title, body) = summarise(&before, &[], &after);
+        assert_eq!(title, "Update a and b");
+        assert_eq!(
+            body,
+            "## Changes\n\n- Removed group **a**\n- New group **b**\
+             \n\n_Submitted with Group Manager._"
+        );
+
+        let unreadable = ["b".to_string(), "c".to_string()];
+        let (_, body) = summarise(&before, &unreadable, &after);
+        assert!(body.ends_with(
+            "\n\nGroups that could not be read from the repository, and may be listed as new: \
+             b, c\n\n_Submitted with Group Manager._"
+        ));
+
+        before = after.clone();
+        let (title, body) = summarise(&before, &[], &after);
+        assert_eq!(title, "Update groups.json");
+        assert!(body.starts_with("No changes to groups.json."));
+    }
+
+    #[tokio::test]
+    async fn submit_from_a_fork() {
+        let upstream =
+            json!({ "name": "groups", "owner": { "login": "infinity" }, "default_branch": "main" });
+        let fork =
+            json!({ "name": "groups", "owner": { "login": "me" }, "default_branch": "main" });
+        let server = MockServer::start(vec![
+            route("GET", "/user", 200, json!({ "login": "me" })),
+            route("GET", "/repos/infinity/groups", 200, &upstream),
+            route(
+                "GET",
+                "/repos/infinity/groups/git/ref/heads/main",
+                200,
+                json!({ "object": { "sha": "base" } }),
+            ),
+            route("POST", "/repos/infinity/groups/forks", 202, &fork),
+            route("GET", "/repos/me/groups", 200, &fork),
+            route(
+                "POST",
+                "/repos/me/groups/git/refs",
+                201,
+                json!({ "object": { "sha": "base" } }),
+            ),
+            route(
+                "GET",
+                "/repos/me/groups/contents/groups.json?ref=update",
+                200,
+                json!({ "sha": "old", "content": "" }),
+            ),
+            route(
+                "PUT",
+                "/repos/me/groups/contents/groups.json",
+                200,
+                json!({}),
+            ),
+            route(
+                "POST",
+                "/repos/infinity/groups/pulls",
+                201,
+                json!({ "number": 7, "html_url": "https://github.com/infinity/groups/pull/7" }),
+            ),
+        ]);
+        let target = Target {
+            owner: "infinity".to_string(),
+            repo: "groups".to_string(),
+            base: String::new(),
+            path: "groups.json".to_string(),
+        };
+
+        let client = GithubClient::new(&server.url).with_token(Some("token"));
+        let pull = submit(&client, &target, "update", "Update", "Body", "{}")
+            .await
+            .unwrap();
+        assert_eq!(pull.number, 7);
+
+        let requests = server.requests();
+        let requested: Vec<String> = requests
+            .iter()
+            .map(|request| format!("{} {}", request.method, request.path))
+            .collect();
+        assert_eq!(
+            requested,
+            [
+                "GET /user",
+                "GET /repos/infinity/groups",
+                "GET /repos/infinity/groups/git/ref/heads/main",
+                "POST /repos/infinity/groups/forks",
+                "GET /repos/me/groups",
+                "POST /repos/me/groups/git/refs",
+                "GET /repos/me/groups/contents/groups.json?ref=update",
+                "PUT /repos/me/groups/contents/groups.json",
+                "POST /repos/infinity/groups/pulls",
+            ]
+        );
+        let body = |index: usize| serde_json::from_str::<Value>(&requests[index].body).unwrap();
+        assert_eq!(
+            body(5),
+            json!({ "ref": "refs/heads/update", "sha": "base" })
+        );
+        assert_eq!(
+            body(7),
+            json!({
+                "message": "Update",
+                "content": STANDARD.encode("{}"),
+                "branch": "update",
+                "sha": "old",
+            })
+        );
+        assert_eq!(
+            body(8),
+            json!({ "title": "Update", "body": "Body", "head": "me:update", "base": "main" })
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use eframe::egui;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// State of a job running on a background thread.
+#[derive(Debug, Clone, Default)]
+pub enum Task<T> {
+    #[default]
+    Idle,
+    Running,
+    Done(T),
+}
+
+pub type SharedTask<T> = Arc<Mutex<Task<T>>>;
+
+impl<T> Task<T> {
+    pub fn is_running(&self) -> bool {
+        matches!(self, Task::Running)
+    }
+}
+
+/// Runs `future` on its own thread and tokio runtime, the same way the
+/// group data fetch does, storing the result in `state` when it finishes.
+pub fn spawn<T, F>(ctx: &egui::Context, state: &SharedTask<T>, future: F)
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    *state.lock().unwrap() = Task::Running;
+    let state = state.clone();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(future);
+        *state.lock().unwrap() = Task::Done(result);
+        ctx.request_repaint();
+    });
+}
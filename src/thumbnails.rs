@@ -0,0 +1,306 @@
+use std::fs::{self, File};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+
+pub const CACHE_DIR: &str = "group-manager.cache";
+/// Previews are at most this wide, so thumbnails are scaled down to it.
+pub const THUMBNAIL_WIDTH: u32 = 400;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What is stored next to a thumbnail. The ETag is kept here rather than in
+/// the file name because it is only known once the thumbnail is found: it is
+/// what the server is asked about. The URL tells a hash collision apart.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Entry {
+    url: String,
+    etag: Option<String>,
+}
+
+/// Result of asking the server about a cached image.
+pub enum Fetched {
+    NotModified,
+    Fresh {
+        etag: Option<String>,
+        bytes: Vec<u8>,
+    },
+}
+
+/// Scaled down copies of remote images on disk, so previews show straight
+/// away and are only downloaded again when their ETag changes. The least
+/// recently used thumbnails are removed once the cache is over its limit.
+#[derive(Debug)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    limit: AtomicU64,
+    size: AtomicU64,
+}
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        Self::new(CACHE_DIR, 100 * 1024 * 1024)
+    }
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: impl Into<PathBuf>, limit: u64) -> Self {
+        let cache = Self {
+            dir: dir.into(),
+            limit: AtomicU64::new(limit),
+            size: AtomicU64::new(0),
+        };
+        let size = cache.files().iter().map(|(_, len, _)| len).sum();
+        cache.size.store(size, Ordering::Relaxed);
+        cache
+    }
+
+    /// Bytes the cache may take up. Takes effect on the next write.
+    pub fn set_limit(&self, limit: u64) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Bytes the cache takes up.
+    pub fn size(&self) -> u64 {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    /// File name for `url`'s thumbnail: its 64-bit FNV-1a hash, which unlike
+    /// `DefaultHasher` stays the same across Rust releases.
+    fn key(url: &str) -> String {
+        let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("{:016x}", hash)
+    }
+
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let key = Self::key(url);
+        (
+            self.dir.join(format!("{}.png", key)),
+            self.dir.join(format!("{}.json", key)),
+        )
+    }
+
+    /// The cached thumbnail for `url` and the ETag it was downloaded with.
+    pub fn get(&self, url: &str) -> Option<(Option<String>, DynamicImage)> {
+        let (image_path, entry_path) = self.paths(url);
+        let entry: Entry = serde_json::from_str(&fs::read_to_string(&entry_path).ok()?).ok()?;
+        if entry.url != url {
+            return None;
+        }
+        let image = image::open(&image_path).ok()?;
+        touch(&image_path);
+        Some((entry.etag, image))
+    }
+
+    /// Scales `image` down to a thumbnail, stores it for `url` and returns it.
+    pub fn put(
+        &self,
+        url: &str,
+        etag: Option<String>,
+        image: &DynamicImage,
+    ) -> Result<DynamicImage, String> {
+        let image = if image.width() > THUMBNAIL_WIDTH {
+            image.resize(THUMBNAIL_WIDTH, u32::MAX, FilterType::Triangle)
+        } else {
+            image.clone()
+        };
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| format!("Error encoding thumbnail of {}: {}", url, e))?;
+
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Error creating {}: {}", self.dir.display(), e))?;
+        let (image_path, entry_path) = self.paths(url);
+        let entry = Entry {
+            url: url.to_string(),
+            etag,
+        };
+        fs::write(&image_path, &png)
+            .map_err(|e| format!("Error writing {}: {}", image_path.display(), e))?;
+        fs::write(&entry_path, serde_json::to_vec(&entry).unwrap())
+            .map_err(|e| format!("Error writing {}: {}", entry_path.display(), e))?;
+        self.evict();
+        Ok(image)
+    }
+
+    /// Thumbnail files with their size and when they were last used.
+    fn files(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((entry.path(), metadata.len(), used))
+            })
+            .collect()
+    }
+
+    /// Removes the least recently used thumbnails until the cache fits in
+    /// its limit.
+    fn evict(&self) {
+        let mut files: Vec<_> = self
+            .files()
+            .into_iter()
+            .filter(|(path, _, _)| path.extension().is_some_and(|ext| ext == "png"))
+            .map(|(path, len, used)| {
+                let entry = path.with_extension("json");
+                let len = len + fs::metadata(&entry).map_or(0, |m| m.len());
+                (path, entry, len, used)
+            })
+            .collect();
+        files.sort_by_key(|(_, _, _, used)| *used);
+        let mut size: u64 = files.iter().map(|(_, _, len, _)| len).sum();
+        let limit = self.limit.load(Ordering::Relaxed);
+        for (image_path, entry_path, len, _) in files {
+            if size <= limit {
+                break;
+            }
+            let _ = fs::remove_file(image_path);
+            let _ = fs::remove_file(entry_path);
+            size -= len;
+        }
+        self.size.store(size, Ordering::Relaxed);
+    }
+
+    /// Deletes every thumbnail.
+    pub fn clear(&self) -> Result<(), String> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)
+                .map_err(|e| format!("Error removing {}: {}", self.dir.display(), e))?;
+        }
+        self.size.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Marks a thumbnail as used, for eviction.
+fn touch(path: &Path) {
+    if let Ok(file) = File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Downloads `url` unless the server says the copy tagged `etag` is
+/// still current.
+pub fn fetch(url: &str, etag: Option<&str>) -> Result<Fetched, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| format!("Error requesting {}: {}", url, e))?;
+    let mut request = client.get(url);
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let response = request
+        .send()
+        .map_err(|e| format!("Error requesting {}: {}", url, e))?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "Error requesting {}: HTTP {}",
+            url,
+            response.status()
+        ));
+    }
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let bytes = response
+        .bytes()
+        .map_err(|e| format!("Error downloading {}: {}", url, e))?;
+    Ok(Fetched::Fresh {
+        etag,
+        bytes: bytes.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use tempfile::TempDir;
+
+    fn image() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, image::Rgba([1, 2, 3, 255])))
+    }
+
+    /// Makes `url`'s thumbnail look last used `secs` seconds ago.
+    fn age(cache: &ThumbnailCache, url: &str, secs: u64) {
+        let (image_path, _) = cache.paths(url);
+        File::options()
+            .write(true)
+            .open(image_path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let dir = TempDir::new().unwrap();
+        let cache = ThumbnailCache::new(dir.path(), u64::MAX);
+        for url in ["https://x/a", "https://x/b", "https://x/c"] {
+            cache.put(url, None, &image()).unwrap();
+        }
+        let entry_size = cache.size() / 3;
+        age(&cache, "https://x/a", 300);
+        age(&cache, "https://x/b", 200);
+        age(&cache, "https://x/c", 100);
+        // Using `a` makes it the most recent, leaving `b` and `c` the oldest.
+        assert!(cache.get("https://x/a").is_some());
+
+        cache.set_limit(entry_size * 2);
+        cache.put("https://x/d", None, &image()).unwrap();
+        assert!(cache.get("https://x/b").is_none());
+        assert!(cache.get("https://x/c").is_none());
+        assert!(cache.get("https://x/a").is_some());
+        assert!(cache.get("https://x/d").is_some());
+        assert_eq!(cache.size(), entry_size * 2);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn clear_empties_the_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let cache = ThumbnailCache::new(&cache_dir, u64::MAX);
+        cache
+            .put("https://x/a", Some("\"1\"".to_string()), &image())
+            .unwrap();
+        assert_eq!(
+            cache.get("https://x/a").unwrap().0.as_deref(),
+            Some("\"1\"")
+        );
+        assert!(cache.size() > 0);
+
+        cache.clear().unwrap();
+        assert_eq!(cache.size(), 0);
+        assert!(cache.get("https://x/a").is_none());
+        assert!(fs::read_dir(&cache_dir).map_or(true, |mut files| files.next().is_none()));
+        // The cache still works after being cleared.
+        cache.put("https://x/a", None, &image()).unwrap();
+        assert!(cache.get("https://x/a").is_some());
+    }
+
+    #[test]
+    fn key_is_stable() {
+        // Published FNV-1a test vectors.
+        assert_eq!(ThumbnailCache::key(""), "cbf29ce484222325");
+        assert_eq!(ThumbnailCache::key("a"), "af63dc4c8601ec8c");
+        assert_eq!(ThumbnailCache::key("foobar"), "85944171f73967e8");
+    }
+}
@@ -1,24 +1,32 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::date::ProjectDate;
+use crate::version::Version;
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct Package {
     pub owner: String,
     pub repoName: String,
-    pub version: String,
+    pub version: Version,
     pub fileName: String,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct Project {
     pub name: String,
-    pub version: String,
-    pub date: String,
+    pub version: Version,
+    pub date: ProjectDate,
     pub changelog: String,
     pub overview: String,
     pub description: String,
+    #[schemars(schema_with = "crate::schema::url_or_empty")]
     pub background: String,
+    #[serde(default)]
+    #[schemars(schema_with = "crate::schema::optional_url_or_empty")]
     pub pageBackground: Option<String>,
     pub variants: Option<Vec<String>>,
     pub package: Option<Package>,
@@ -27,8 +35,8 @@ impl Project {
     pub fn new(name: String) -> Self {
         Self {
             name,
-            version: String::new(),
-            date: String::new(),
+            version: Version::default(),
+            date: ProjectDate::default(),
             changelog: String::new(),
             overview: String::new(),
             description: String::new(),
@@ -40,22 +48,26 @@ impl Project {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct Palette {
+    #[schemars(schema_with = "crate::schema::colour_or_empty")]
     pub primary: String,
+    #[schemars(schema_with = "crate::schema::colour_or_empty")]
     pub secondary: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct BetaProject {
+    #[schemars(schema_with = "crate::schema::url_or_empty")]
     pub background: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct GroupData {
     pub name: String,
     pub projects: Vec<Project>,
     pub beta: BetaProject,
+    #[schemars(schema_with = "crate::schema::url_or_empty")]
     pub logo: String,
     pub update: Option<bool>,
     pub path: String,
@@ -82,3 +94,101 @@ impl GroupData {
         }
     }
 }
+
+/// `groups` as groups.json text. Groups are written in name order so saving
+/// the same data always gives the same file and commits only show edits.
+pub fn groups_json(groups: &HashMap<String, GroupData>) -> String {
+    let sorted: BTreeMap<&String, &GroupData> = groups.iter().collect();
+    serde_json::to_string_pretty(&sorted).unwrap()
+}
+
+/// A group as written to groups.json: either loaded, or the raw JSON of one
+/// that failed to load.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Written<'a> {
+    Group(&'a GroupData),
+    Broken(serde_json::Value),
+}
+
+/// Like `groups_json`, with the groups that failed to load (`LoadReport.broken`)
+/// written back as they were read so saving a document doesn't delete them.
+/// A loaded group replaces a broken one with the same name.
+pub fn document_json(
+    groups: &HashMap<String, GroupData>,
+    broken: &HashMap<String, String>,
+) -> String {
+    let mut sorted: BTreeMap<&String, Written> = broken
+        .iter()
+        .filter_map(|(name, raw)| Some((name, Written::Broken(serde_json::from_str(raw).ok()?))))
+        .collect();
+    sorted.extend(
+        groups
+            .iter()
+            .map(|(name, group)| (name, Written::Group(group))),
+    );
+    serde_json::to_string_pretty(&sorted).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_json_is_sorted() {
+        let groups: HashMap<String, GroupData> = ["c", "a", "d", "b"]
+            .into_iter()
+            .map(|name| (name.to_string(), GroupData::new(name.to_uppercase())))
+            .collect();
+        let json = groups_json(&groups);
+        let positions: Vec<usize> = ["\"a\"", "\"b\"", "\"c\"", "\"d\""]
+            .iter()
+            .map(|key| json.find(key).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        // A map with the same groups but its own hash seed.
+        let rehashed: HashMap<String, GroupData> = groups.into_iter().collect();
+        assert_eq!(json, groups_json(&rehashed));
+    }
+
+    #[test]
+    fn new_groups_match_the_schema() {
+        let mut group = GroupData::new("A".to_string());
+        group.projects.push(Project::new("P".to_string()));
+        let mut groups = HashMap::from([("a".to_string(), group)]);
+        let issues = |groups: &HashMap<String, GroupData>| {
+            crate::validate::load_groups(&groups_json(groups), crate::date::ISO_8601)
+                .unwrap()
+                .issues
+                .into_iter()
+                .map(|issue| issue.pointer)
+                .collect::<Vec<_>>()
+        };
+        assert!(issues(&groups).is_empty());
+
+        let group = groups.get_mut("a").unwrap();
+        group.palette.primary = "red".to_string();
+        group.logo = "logo.png".to_string();
+        group.projects[0].pageBackground = Some("https://example.com/bg.png".to_string());
+        let mut found = issues(&groups);
+        found.sort();
+        assert_eq!(found, ["/a/logo", "/a/palette/primary"]);
+    }
+
+    #[test]
+    fn document_json_keeps_broken_groups() {
+        let groups = HashMap::from([("a".to_string(), GroupData::new("A".to_string()))]);
+        let broken = HashMap::from([
+            ("b".to_string(), r#"{"name": 5}"#.to_string()),
+            ("a".to_string(), r#"{"name": 6}"#.to_string()),
+        ]);
+        let written: serde_json::Value =
+            serde_json::from_str(&document_json(&groups, &broken)).unwrap();
+        assert_eq!(written["a"], serde_json::to_value(&groups["a"]).unwrap());
+        assert_eq!(written["b"], serde_json::json!({ "name": 5 }));
+        assert_eq!(
+            document_json(&groups, &HashMap::new()),
+            groups_json(&groups)
+        );
+    }
+}
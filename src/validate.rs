@@ -0,0 +1,708 @@
+use chrono::NaiveDate;
+use regex::Regex;
+use schemars::schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::date::{self, DateChange};
+use crate::schema;
+use crate::types::GroupData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    pub group: Option<String>,
+    pub pointer: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(
+            f,
+            "{}:{} {}: {}",
+            self.line, self.column, pointer, self.message
+        )
+    }
+}
+
+/// Result of a tolerant load: every group that deserialized cleanly, the raw
+/// JSON of the ones that didn't, and every schema violation found on the way.
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub groups: HashMap<String, GroupData>,
+    pub broken: HashMap<String, String>,
+    pub issues: Vec<Issue>,
+    pub normalised_dates: Vec<DateChange>,
+    /// Why the document couldn't be loaded at all, if it couldn't.
+    pub error: Option<String>,
+}
+
+impl LoadReport {
+    /// A report for a document that couldn't be read or parsed at all.
+    pub fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+
+    pub fn has_problems(&self) -> bool {
+        self.error.is_some()
+            || !self.broken.is_empty()
+            || !self.issues.is_empty()
+            || !self.normalised_dates.is_empty()
+    }
+
+    /// Rewrites project dates in `format` and drops the date warnings that
+    /// no longer apply. Dates that can't be parsed keep their warning.
+    pub fn normalise_dates(&mut self, format: &str) {
+        self.normalised_dates = date::normalise_dates(&mut self.groups, format);
+        let changed: Vec<&str> = self
+            .normalised_dates
+            .iter()
+            .map(|change| change.pointer.as_str())
+            .collect();
+        self.issues
+            .retain(|issue| !changed.contains(&issue.pointer.as_str()));
+    }
+}
+
+/// Loads a groups.json document, keeping every group that is valid instead of
+/// failing on the first one that isn't. Only a syntax error in the document
+/// itself is fatal. Project dates are checked against `date_format`.
+pub fn load_groups(text: &str, date_format: &str) -> Result<LoadReport, Issue> {
+    let value: Value = serde_json::from_str(text).map_err(|e| Issue {
+        severity: Severity::Error,
+        group: None,
+        pointer: String::new(),
+        line: e.line(),
+        column: e.column(),
+        message: format!("Error parsing: {}", e),
+    })?;
+
+    let root = schema::groups_schema(date_format);
+    let mut report = LoadReport::default();
+    for violation in Validator::new(&root).validate(&value) {
+        let group = pointer_segments(&violation.pointer).into_iter().next();
+        report.issues.push(violation.into_issue(text, group));
+    }
+
+    let Value::Object(map) = value else {
+        return Ok(report);
+    };
+    for (name, group) in map {
+        match serde_json::from_value::<GroupData>(group.clone()) {
+            Ok(data) => {
+                report.groups.insert(name, data);
+            }
+            Err(e) => {
+                if !report
+                    .issues
+                    .iter()
+                    .any(|i| i.group.as_ref() == Some(&name) && i.severity == Severity::Error)
+                {
+                    let violation = Violation {
+                        severity: Severity::Error,
+                        pointer: format!("/{}", escape_segment(&name)),
+                        message: format!("Error deserializing: {}", e),
+                    };
+                    report
+                        .issues
+                        .push(violation.into_issue(text, Some(name.clone())));
+                }
+                report
+                    .broken
+                    .insert(name, serde_json::to_string_pretty(&group).unwrap());
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Parses the raw JSON of a single group, as edited in the repair window.
+/// Violations are reported relative to the group itself.
+pub fn load_group(text: &str, date_format: &str) -> Result<(GroupData, Vec<Issue>), Vec<Issue>> {
+    let value: Value = serde_json::from_str(text).map_err(|e| {
+        vec![Issue {
+            severity: Severity::Error,
+            group: None,
+            pointer: String::new(),
+            line: e.line(),
+            column: e.column(),
+            message: format!("Error parsing: {}", e),
+        }]
+    })?;
+
+    let root = schema::groups_schema(date_format);
+    let validator = Validator::new(&root);
+    let mut violations = Vec::new();
+    validator.check(
+        &Schema::new_ref("#/definitions/GroupData".to_string()),
+        &value,
+        "",
+        &mut violations,
+    );
+    let issues: Vec<Issue> = violations
+        .into_iter()
+        .map(|violation| violation.into_issue(text, None))
+        .collect();
+
+    match serde_json::from_value::<GroupData>(value) {
+        Ok(data) => Ok((data, issues)),
+        Err(e) if issues.iter().all(|i| i.severity == Severity::Warning) => Err(vec![Issue {
+            severity: Severity::Error,
+            group: None,
+            pointer: String::new(),
+            line: 1,
+            column: 1,
+            message: format!("Error deserializing: {}", e),
+        }]),
+        Err(_) => Err(issues),
+    }
+}
+
+struct Violation {
+    severity: Severity,
+    pointer: String,
+    message: String,
+}
+
+impl Violation {
+    fn into_issue(self, text: &str, group: Option<String>) -> Issue {
+        let (line, column) = locate(text, &self.pointer).unwrap_or((1, 1));
+        Issue {
+            severity: self.severity,
+            group,
+            pointer: self.pointer,
+            line,
+            column,
+            message: self.message,
+        }
+    }
+}
+
+/// Checks a value against the subset of JSON Schema that schemars emits for
+/// our types: `$ref`, `type`, `const`, `anyOf`, `required`, `properties`,
+/// `additionalProperties`, `items`, `pattern` and `format`.
+struct Validator<'a> {
+    root: &'a RootSchema,
+    patterns: HashMap<String, Regex>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(root: &'a RootSchema) -> Self {
+        let mut patterns = HashMap::new();
+        for schema in root.definitions.values() {
+            collect_patterns(schema, &mut patterns);
+        }
+        Self { root, patterns }
+    }
+
+    fn validate(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let schema = Schema::Object(self.root.schema.clone());
+        self.check(&schema, value, "", &mut violations);
+        violations
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&'a SchemaObject> {
+        let name = reference.strip_prefix("#/definitions/")?;
+        match self.root.definitions.get(name)? {
+            Schema::Object(object) => Some(object),
+            Schema::Bool(_) => None,
+        }
+    }
+
+    fn check(&self, schema: &Schema, value: &Value, pointer: &str, out: &mut Vec<Violation>) {
+        let Schema::Object(object) = schema else {
+            return;
+        };
+        if let Some(reference) = &object.reference {
+            if let Some(target) = self.resolve(reference) {
+                self.check(&Schema::Object(target.clone()), value, pointer, out);
+            }
+            return;
+        }
+
+        if let Some(any_of) = object.subschemas.as_ref().and_then(|s| s.any_of.as_ref()) {
+            // The candidate with the fewest errors, then the fewest warnings.
+            let mut best: Option<(usize, Vec<Violation>)> = None;
+            for candidate in any_of {
+                let mut attempt = Vec::new();
+                self.check(candidate, value, pointer, &mut attempt);
+                if attempt.is_empty() {
+                    return;
+                }
+                let errors = attempt
+                    .iter()
+                    .filter(|v| v.severity == Severity::Error)
+                    .count();
+                if best
+                    .as_ref()
+                    .is_none_or(|(e, b)| (errors, attempt.len()) < (*e, b.len()))
+                {
+                    best = Some((errors, attempt));
+                }
+            }
+            out.extend(best.map(|(_, b)| b).unwrap_or_default());
+            return;
+        }
+
+        if let Some(expected) = &object.const_value {
+            if value != expected {
+                out.push(Violation {
+                    severity: Severity::Error,
+                    pointer: pointer.to_string(),
+                    message: format!("expected {}, found {}", expected, value),
+                });
+            }
+            return;
+        }
+
+        if let Some(instance_type) = &object.instance_type {
+            if !matches_type(instance_type, value) {
+                out.push(Violation {
+                    severity: Severity::Error,
+                    pointer: pointer.to_string(),
+                    message: format!(
+                        "expected {}, found {}",
+                        describe_type(instance_type),
+                        value_type(value)
+                    ),
+                });
+                return;
+            }
+        }
+
+        match value {
+            Value::Object(map) => {
+                let Some(validation) = &object.object else {
+                    return;
+                };
+                for field in &validation.required {
+                    if !map.contains_key(field) {
+                        out.push(Violation {
+                            severity: Severity::Error,
+                            pointer: pointer.to_string(),
+                            message: format!("missing field `{}`", field),
+                        });
+                    }
+                }
+                for (key, child) in map {
+                    let child_pointer = format!("{}/{}", pointer, escape_segment(key));
+                    if let Some(property) = validation.properties.get(key) {
+                        self.check(property, child, &child_pointer, out);
+                    } else if let Some(additional) = &validation.additional_properties {
+                        self.check(additional, child, &child_pointer, out);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(SingleOrVec::Single(item)) =
+                    object.array.as_ref().and_then(|a| a.items.as_ref())
+                {
+                    for (index, child) in items.iter().enumerate() {
+                        self.check(item, child, &format!("{}/{}", pointer, index), out);
+                    }
+                }
+            }
+            Value::String(string) => {
+                if let Some(pattern) = object.string.as_ref().and_then(|s| s.pattern.as_ref()) {
+                    if let Some(regex) = self.patterns.get(pattern) {
+                        if !regex.is_match(string) {
+                            out.push(Violation {
+                                severity: Severity::Warning,
+                                pointer: pointer.to_string(),
+                                message: format!("{:?} does not match {}", string, pattern),
+                            });
+                        }
+                    }
+                }
+                if let Some(format) = &object.format {
+                    if let Some(message) = check_format(format, string) {
+                        out.push(Violation {
+                            severity: Severity::Warning,
+                            pointer: pointer.to_string(),
+                            message,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Compiles every `pattern` in `schema` and the schemas inside it.
+fn collect_patterns(schema: &Schema, patterns: &mut HashMap<String, Regex>) {
+    let Schema::Object(object) = schema else {
+        return;
+    };
+    if let Some(pattern) = object.string.as_ref().and_then(|s| s.pattern.as_ref()) {
+        if let Ok(regex) = Regex::new(pattern) {
+            patterns.insert(pattern.clone(), regex);
+        }
+    }
+    if let Some(validation) = &object.object {
+        for property in validation.properties.values() {
+            collect_patterns(property, patterns);
+        }
+    }
+    if let Some(any_of) = object.subschemas.as_ref().and_then(|s| s.any_of.as_ref()) {
+        for candidate in any_of {
+            collect_patterns(candidate, patterns);
+        }
+    }
+}
+
+fn check_format(format: &str, value: &str) -> Option<String> {
+    match format {
+        "date" => {
+            let valid =
+                value.len() == 10 && NaiveDate::parse_from_str(value, date::ISO_8601).is_ok();
+            (!valid).then(|| format!("{:?} is not an ISO-8601 date (YYYY-MM-DD)", value))
+        }
+        "uri" => {
+            let valid = (value.starts_with("https://") || value.starts_with("http://"))
+                && !value.contains(char::is_whitespace);
+            (!valid).then(|| format!("{:?} is not an http(s) URL", value))
+        }
+        // A configured date format, see `date::schema_format`.
+        format if format.contains('%') => NaiveDate::parse_from_str(value, format)
+            .is_err()
+            .then(|| format!("{:?} is not a date in the format {}", value, format)),
+        _ => None,
+    }
+}
+
+fn matches_type(instance_type: &SingleOrVec<InstanceType>, value: &Value) -> bool {
+    let matches = |t: &InstanceType| match t {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => value.is_i64() || value.is_u64(),
+    };
+    match instance_type {
+        SingleOrVec::Single(t) => matches(t),
+        SingleOrVec::Vec(types) => types.iter().any(matches),
+    }
+}
+
+fn describe_type(instance_type: &SingleOrVec<InstanceType>) -> String {
+    let name = |t: &InstanceType| {
+        serde_json::to_value(t)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default()
+    };
+    match instance_type {
+        SingleOrVec::Single(t) => name(t),
+        SingleOrVec::Vec(types) => types.iter().map(name).collect::<Vec<_>>().join(" or "),
+    }
+}
+
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+pub fn escape_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn pointer_segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Finds the 1-based line and column of the value a JSON pointer refers to by
+/// scanning the source text, since `serde_json::Value` keeps no positions.
+pub fn locate(text: &str, pointer: &str) -> Option<(usize, usize)> {
+    let segments = pointer_segments(pointer);
+    let mut scanner = Scanner {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    scanner.skip_whitespace();
+    for segment in &segments {
+        match scanner.peek()? {
+            b'{' => scanner.enter_key(segment)?,
+            b'[' => scanner.enter_index(segment.parse().ok()?)?,
+            _ => return None,
+        }
+    }
+    Some(line_column(text, scanner.pos))
+}
+
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        serde_json::from_slice(&self.bytes[start..self.pos]).ok()
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'"' => {
+                self.string()?;
+            }
+            open @ (b'{' | b'[') => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.peek()? == close {
+                    self.pos += 1;
+                    return Some(());
+                }
+                loop {
+                    if open == b'{' {
+                        self.string()?;
+                        self.expect(b':')?;
+                    }
+                    self.skip_value()?;
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        c if c == close => {
+                            self.pos += 1;
+                            break;
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            _ => {
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r')
+                ) {
+                    self.pos += 1;
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn enter_key(&mut self, key: &str) -> Option<()> {
+        self.expect(b'{')?;
+        loop {
+            self.skip_whitespace();
+            if self.peek()? == b'}' {
+                return None;
+            }
+            let name = self.string()?;
+            self.expect(b':')?;
+            self.skip_whitespace();
+            if name == key {
+                return Some(());
+            }
+            self.skip_value()?;
+            self.expect(b',')?;
+        }
+    }
+
+    fn enter_index(&mut self, index: usize) -> Option<()> {
+        self.expect(b'[')?;
+        for _ in 0..index {
+            self.skip_value()?;
+            self.expect(b',')?;
+        }
+        self.skip_whitespace();
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Project;
+    use serde_json::json;
+
+    /// groups.json text with group `key` set to `group`, as written by hand.
+    fn document(key: &str, group: Value) -> String {
+        serde_json::to_string_pretty(&json!({ "first": valid_group(), key: group })).unwrap()
+    }
+
+    fn valid_group() -> Value {
+        let mut group = GroupData::new("G".to_string());
+        let mut project = Project::new("P".to_string());
+        *project.date.raw_mut() = "2024-01-31".to_string();
+        group.projects.push(project.clone());
+        project.name = "Q".to_string();
+        group.projects.push(project);
+        serde_json::to_value(group).unwrap()
+    }
+
+    /// The line `needle` first appears on, 1-based.
+    fn line_of(text: &str, needle: &str) -> usize {
+        text.lines().position(|line| line.contains(needle)).unwrap() + 1
+    }
+
+    fn only_issue(text: &str) -> Issue {
+        let mut issues = load_groups(text, date::ISO_8601).unwrap().issues;
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        issues.remove(0)
+    }
+
+    #[test]
+    fn locates_nested_pointers() {
+        let text = "{\n  \"a\": [1, {\"b\": \n    {\"c\": true}}],\n  \"d\": 2\n}";
+        assert_eq!(locate(text, ""), Some((1, 1)));
+        assert_eq!(locate(text, "/a/1/b"), Some((3, 5)));
+        assert_eq!(locate(text, "/a/1/b/c"), Some((3, 11)));
+        assert_eq!(locate(text, "/d"), Some((4, 8)));
+        assert_eq!(locate(text, "/a/5"), None);
+        assert_eq!(locate(text, "/missing"), None);
+    }
+
+    #[test]
+    fn locates_escaped_keys() {
+        let text = r#"{"x": 1, "a/b": {"c~d": {"say \"hi\"": 2}}}"#;
+        let pointer = format!(
+            "/{}/{}/{}",
+            escape_segment("a/b"),
+            escape_segment("c~d"),
+            escape_segment("say \"hi\"")
+        );
+        assert_eq!(pointer, "/a~1b/c~0d/say \"hi\"");
+        assert_eq!(locate(text, &pointer), Some((1, 40)));
+        // "~01" is a literal "~1", not a "/".
+        assert_eq!(pointer_segments("/a~01b"), ["a~1b"]);
+    }
+
+    #[test]
+    fn issues_in_escaped_groups_point_at_them() {
+        let mut group = valid_group();
+        group["palette"]["primary"] = json!("red");
+        let text = document("a/b ~ \"c\"", group);
+        let issue = only_issue(&text);
+        assert_eq!(issue.severity, Severity::Warning);
+        assert_eq!(issue.group.as_deref(), Some("a/b ~ \"c\""));
+        assert_eq!(issue.pointer, "/a~1b ~0 \"c\"/palette/primary");
+        assert_eq!(issue.line, line_of(&text, "\"red\""));
+    }
+
+    #[test]
+    fn pattern_and_format_failures_map_to_their_line() {
+        let mut group = valid_group();
+        group["projects"][1]["version"] = json!("1.0");
+        let text = document("second", group);
+        let issue = only_issue(&text);
+        assert_eq!(issue.pointer, "/second/projects/1/version");
+        assert_eq!(issue.line, line_of(&text, "\"1.0\""));
+        assert!(issue.message.contains("does not match"));
+
+        let mut group = valid_group();
+        group["projects"][1]["date"] = json!("soon");
+        let text = document("second", group);
+        let issue = only_issue(&text);
+        assert_eq!(issue.pointer, "/second/projects/1/date");
+        assert_eq!(issue.line, line_of(&text, "\"soon\""));
+        assert!(issue.message.contains("ISO-8601"));
+    }
+
+    #[test]
+    fn any_of_and_options() {
+        // Empty placeholders and nulls for the optional fields are fine.
+        let text = document("second", valid_group());
+        assert!(load_groups(&text, date::ISO_8601)
+            .unwrap()
+            .issues
+            .is_empty());
+
+        let mut group = valid_group();
+        group["projects"][0]["pageBackground"] = json!("not a url");
+        let text = document("second", group);
+        let issue = only_issue(&text);
+        assert_eq!(issue.severity, Severity::Warning);
+        assert_eq!(issue.pointer, "/second/projects/0/pageBackground");
+        assert_eq!(issue.line, line_of(&text, "\"not a url\""));
+
+        // The package branch is reported rather than the null one.
+        let mut group = valid_group();
+        group["projects"][0]["package"] =
+            json!({ "owner": 5, "repoName": "r", "version": "1.0.0", "fileName": "f" });
+        let text = document("second", group);
+        let issue = only_issue(&text);
+        assert_eq!(issue.severity, Severity::Error);
+        assert_eq!(issue.pointer, "/second/projects/0/package/owner");
+        assert_eq!(issue.line, line_of(&text, "\"owner\": 5"));
+        assert!(issue.message.contains("expected string"));
+
+        let mut group = valid_group();
+        group["hide"] = json!("yes");
+        let report = load_groups(&document("second", group), date::ISO_8601).unwrap();
+        assert!(report.broken.contains_key("second"));
+        assert!(report
+            .issues
+            .iter()
+            .any(|issue| issue.pointer == "/second/hide" && issue.severity == Severity::Error));
+    }
+}
@@ -0,0 +1,200 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+pub const SEMVER: &str =
+    r"^v?(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)(-[0-9A-Za-z.-]+)?(\+[0-9A-Za-z.-]+)?$";
+
+/// A version as written in groups.json. The text is kept exactly as entered so
+/// existing files round-trip unchanged, and is parsed as semver on demand,
+/// accepting a leading `v`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Version(String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+    PreRelease,
+}
+
+impl Bump {
+    pub const ALL: [Bump; 4] = [Bump::Major, Bump::Minor, Bump::Patch, Bump::PreRelease];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Bump::Major => "Major",
+            Bump::Minor => "Minor",
+            Bump::Patch => "Patch",
+            Bump::PreRelease => "Pre-release",
+        }
+    }
+}
+
+impl Version {
+    pub fn new(version: &str) -> Self {
+        Self(version.to_string())
+    }
+
+    pub fn raw_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.trim().is_empty()
+    }
+
+    fn has_prefix(&self) -> bool {
+        self.0.trim().starts_with(['v', 'V'])
+    }
+
+    pub fn parse(&self) -> Result<semver::Version, String> {
+        let trimmed = self.0.trim();
+        let bare = trimmed.strip_prefix(['v', 'V']).unwrap_or(trimmed);
+        semver::Version::parse(bare)
+            .map_err(|e| format!("\"{}\" is not a semantic version: {}", self.0, e))
+    }
+
+    /// Returns the bumped version, keeping a leading `v` if there was one.
+    /// Bumping the patch of a pre-release releases it (`1.2.0-beta.2` becomes
+    /// `1.2.0`), and a pre-release bump counts up the last numeric identifier
+    /// or starts a `beta.1` on the next patch.
+    pub fn bumped(&self, bump: Bump) -> Result<Version, String> {
+        let mut version = self.parse()?;
+        version.build = semver::BuildMetadata::EMPTY;
+        match bump {
+            Bump::Major => {
+                version.major += 1;
+                version.minor = 0;
+                version.patch = 0;
+                version.pre = semver::Prerelease::EMPTY;
+            }
+            Bump::Minor => {
+                version.minor += 1;
+                version.patch = 0;
+                version.pre = semver::Prerelease::EMPTY;
+            }
+            Bump::Patch => {
+                if version.pre.is_empty() {
+                    version.patch += 1;
+                }
+                version.pre = semver::Prerelease::EMPTY;
+            }
+            Bump::PreRelease => {
+                let pre = if version.pre.is_empty() {
+                    version.patch += 1;
+                    "beta.1".to_string()
+                } else {
+                    next_pre_release(version.pre.as_str())
+                };
+                version.pre = semver::Prerelease::new(&pre).map_err(|e| e.to_string())?;
+            }
+        }
+        let prefix = if self.has_prefix() { "v" } else { "" };
+        Ok(Version(format!("{}{}", prefix, version)))
+    }
+
+    /// Compares two versions by semver precedence, or `None` if either one
+    /// doesn't parse.
+    pub fn compare(&self, other: &Version) -> Option<Ordering> {
+        Some(self.parse().ok()?.cmp_precedence(&other.parse().ok()?))
+    }
+}
+
+fn next_pre_release(pre: &str) -> String {
+    let mut parts: Vec<String> = pre.split('.').map(str::to_string).collect();
+    match parts.last().and_then(|last| last.parse::<u64>().ok()) {
+        Some(number) => *parts.last_mut().unwrap() = (number + 1).to_string(),
+        None => parts.push("1".to_string()),
+    }
+    parts.join(".")
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl JsonSchema for Version {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "Version".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        let version = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(SEMVER.to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        crate::schema::or_empty(version, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bumped(version: &str, bump: Bump) -> String {
+        Version::new(version).bumped(bump).unwrap().to_string()
+    }
+
+    #[test]
+    fn bumps_release_versions() {
+        assert_eq!(bumped("1.2.3", Bump::Major), "2.0.0");
+        assert_eq!(bumped("1.2.3", Bump::Minor), "1.3.0");
+        assert_eq!(bumped("1.2.3", Bump::Patch), "1.2.4");
+        assert_eq!(bumped("1.2.3", Bump::PreRelease), "1.2.4-beta.1");
+        // Build metadata doesn't carry over.
+        assert_eq!(bumped("1.2.3+build.7", Bump::Patch), "1.2.4");
+    }
+
+    #[test]
+    fn keeps_the_v_prefix() {
+        assert_eq!(bumped("v1.2.3", Bump::Minor), "v1.3.0");
+        assert_eq!(bumped(" V1.2.3 ", Bump::Patch), "v1.2.4");
+        assert_eq!(bumped("v1.2.3-beta", Bump::PreRelease), "v1.2.3-beta.1");
+    }
+
+    #[test]
+    fn bumps_pre_releases() {
+        assert_eq!(bumped("1.2.0-beta.2", Bump::Patch), "1.2.0");
+        assert_eq!(bumped("1.2.0-beta.2", Bump::Minor), "1.3.0");
+        assert_eq!(bumped("1.2.0-beta.2", Bump::Major), "2.0.0");
+        assert_eq!(bumped("1.2.0-beta", Bump::PreRelease), "1.2.0-beta.1");
+        assert_eq!(bumped("1.2.0-beta.9", Bump::PreRelease), "1.2.0-beta.10");
+        assert_eq!(
+            bumped("1.2.0-rc.1.alpha", Bump::PreRelease),
+            "1.2.0-rc.1.alpha.1"
+        );
+        assert_eq!(next_pre_release("7"), "8");
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        for version in ["", "1.2", "v", "1.2.3.4", "one.two.three", "1.2.3-"] {
+            assert!(
+                Version::new(version).bumped(Bump::Patch).is_err(),
+                "{}",
+                version
+            );
+        }
+        assert_eq!(Version::new("1.2").compare(&Version::new("1.2.0")), None);
+        assert_eq!(
+            Version::new("v1.2.0-beta.10").compare(&Version::new("1.2.0-beta.9")),
+            Some(Ordering::Greater)
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use eframe::egui::{self, Color32};
+use std::cmp::Ordering;
+
+use crate::date::ProjectDate;
+use crate::markdown;
+use crate::version::{Bump, Version};
+
+/// What was done this frame in a window that asks before changing anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirm {
+    None,
+    Apply,
+    Cancel,
+}
+
+/// Text field for a semantic version with bump buttons and inline
+/// validation. Returns the bump that was applied this frame, if any.
+pub fn version_edit(
+    ui: &mut egui::Ui,
+    version: &mut Version,
+    upstream: Option<&Version>,
+) -> Option<Bump> {
+    let mut applied = None;
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(version.raw_mut());
+            for bump in Bump::ALL {
+                let button =
+                    ui.add_enabled(version.parse().is_ok(), egui::Button::new(bump.label()));
+                if button.on_hover_text("Bump version").clicked() {
+                    if let Ok(bumped) = version.bumped(bump) {
+                        *version = bumped;
+                        applied = Some(bump);
+                    }
+                }
+            }
+        });
+
+        if version.is_empty() {
+            return;
+        }
+        if let Err(e) = version.parse() {
+            ui.colored_label(Color32::LIGHT_RED, e);
+            return;
+        }
+        if let Some(upstream) = upstream {
+            if version.compare(upstream) == Some(Ordering::Less) {
+                ui.colored_label(
+                    Color32::LIGHT_RED,
+                    format!("Lower than the upstream version {}", upstream),
+                );
+            }
+        }
+    });
+    applied
+}
+
+/// Text field for a project date with a calendar picker, a "Today" button and
+/// inline validation. Picked dates are written in `format`. `id_source` keeps
+/// the picker's popup apart from other date fields open at the same time.
+pub fn date_edit(ui: &mut egui::Ui, id_source: &str, date: &mut ProjectDate, format: &str) {
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(date.raw_mut());
+            let today = chrono::Local::now().date_naive();
+            let mut picked = date.parse_in(format).unwrap_or(today);
+            let before = picked;
+            ui.add(egui_extras::DatePickerButton::new(&mut picked).id_source(id_source));
+            if picked != before {
+                date.set(picked, format);
+            }
+            if ui.button("Today").clicked() {
+                date.set(today, format);
+            }
+        });
+
+        if date.is_empty() {
+            return;
+        }
+        if let Err(e) = date.parse_in(format) {
+            ui.colored_label(Color32::LIGHT_RED, e);
+        }
+    });
+}
+
+/// Multiline Markdown field with the rendered preview beside it and any
+/// warnings about links or syntax the site won't handle underneath.
+pub fn markdown_edit(ui: &mut egui::Ui, label: &str, text: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::TextEdit::multiline(text).desired_width(300.0));
+        ui.group(|ui| {
+            ui.set_width(400.0);
+            ui.vertical(|ui| markdown::preview(ui, text));
+        });
+    });
+    for warning in markdown::check(text) {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!("{} line {}: {}", label, warning.line, warning.message),
+        );
+    }
+}
+
+/// `#rrggbb` as a colour, `None` if it is not one.
+pub fn parse_colour(hex: &str) -> Option<Color32> {
+    let hex = hex.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Preview of the image at `url`, decoded the same way whatever its format.
+/// When it cannot be fetched or decoded a placeholder with the reason is
+/// shown instead.
+pub fn image_preview(ui: &mut egui::Ui, url: &str, max_width: f32) {
+    let url = url.trim();
+    if url.is_empty() {
+        return;
+    }
+    let image = egui::Image::new(url.to_string()).max_width(max_width);
+    match image.load_for_size(ui.ctx(), ui.available_size()) {
+        Ok(_) => {
+            ui.add(image);
+        }
+        Err(e) => {
+            egui::Frame::group(ui.style())
+                .fill(Color32::from_rgb(60, 20, 20))
+                .show(ui, |ui| {
+                    ui.set_max_width(max_width);
+                    ui.vertical(|ui| {
+                        ui.colored_label(Color32::LIGHT_RED, "Image could not be shown");
+                        ui.small(e.to_string());
+                    });
+                });
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::bulk_edit::BulkEditWindow;
+use crate::history::History;
+use crate::image_check::ImageCheck;
+use crate::raw_editor::RawEditor;
+use crate::release::ReleaseWizard;
+use crate::release_check::ProjectCheck;
+use crate::release_sync::SyncWizard;
+use crate::submit::SubmitWindow;
+use crate::task::SharedTask;
+use crate::types::{self, GroupData};
+use crate::validate::{self, Issue, LoadReport};
+
+/// Where a document was loaded from and is saved back to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Source {
+    #[default]
+    New,
+    Url(String),
+    File(PathBuf),
+}
+
+impl Source {
+    pub fn label(&self) -> String {
+        match self {
+            Source::New => "Untitled".to_string(),
+            // raw.githubusercontent.com/<owner>/<repo>/<branch>/<file>
+            Source::Url(url) => {
+                let parts: Vec<&str> = url.trim_end_matches('/').rsplit('/').collect();
+                match parts.as_slice() {
+                    [_, branch, repo, ..] => format!("{}@{}", repo, branch),
+                    _ => url.clone(),
+                }
+            }
+            Source::File(path) => path.display().to_string(),
+        }
+    }
+
+    /// The file "Save" writes to. Documents that didn't come from a file
+    /// are saved as groups.json next to the app, as before.
+    pub fn save_path(&self) -> PathBuf {
+        match self {
+            Source::File(path) => path.clone(),
+            _ => PathBuf::from("groups.json"),
+        }
+    }
+}
+
+/// A group that failed to load, opened as raw JSON so it can be fixed by hand.
+#[derive(Debug, Clone)]
+pub struct Repair {
+    pub name: String,
+    pub text: String,
+    pub issues: Vec<Issue>,
+}
+
+impl Repair {
+    /// Opens `text` for repair. The issues are found again on `text` itself
+    /// so their lines and columns point into what the window shows rather
+    /// than into the original file.
+    pub fn new(name: String, text: String, date_format: &str) -> Self {
+        let issues = match validate::load_group(&text, date_format) {
+            Ok((_, issues)) | Err(issues) => issues,
+        };
+        Self { name, text, issues }
+    }
+}
+
+/// One open groups.json with everything that belongs to it: its own undo
+/// history, editor selection and open windows.
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    pub source: Source,
+    pub group_data: Arc<Mutex<HashMap<String, GroupData>>>,
+    pub load_report: Arc<Mutex<LoadReport>>,
+    /// The groups as the repository has them, for diffs and pull requests.
+    pub upstream: Arc<Mutex<HashMap<String, GroupData>>>,
+    /// Names of the repository's groups that failed to load, which are
+    /// missing from `upstream`.
+    pub upstream_unreadable: Arc<Mutex<Vec<String>>>,
+    /// The groups as last loaded or saved, to tell if there are unsaved edits.
+    pub saved: Arc<Mutex<HashMap<String, GroupData>>>,
+    pub repair: Option<Repair>,
+    pub raw_editor: RawEditor,
+    pub history: History,
+    pub release: Option<ReleaseWizard>,
+    pub sync: Option<SyncWizard>,
+    pub bulk_edit: Option<BulkEditWindow>,
+    pub submit: Option<SubmitWindow>,
+    pub release_checks: SharedTask<Vec<ProjectCheck>>,
+    pub image_checks: SharedTask<Vec<ImageCheck>>,
+    pub selected_group: String,
+    pub selected_project: Option<usize>,
+    pub new_group_name: String,
+    pub new_project_name: String,
+}
+
+impl Document {
+    pub fn is_empty(&self) -> bool {
+        self.group_data.lock().unwrap().is_empty()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        *self.group_data.lock().unwrap() != *self.saved.lock().unwrap()
+    }
+
+    pub fn title(&self) -> String {
+        if self.is_dirty() {
+            format!("{} *", self.source.label())
+        } else {
+            self.source.label()
+        }
+    }
+
+    /// Returns a callback that stores a load result in this document. It
+    /// can be sent to the thread doing the loading. `is_upstream` marks the
+    /// data as what the repository has.
+    pub fn loader(
+        &self,
+        date_format: &str,
+        is_upstream: bool,
+    ) -> impl FnOnce(Result<LoadReport, String>) + Send + 'static {
+        let group_data = self.group_data.clone();
+        let load_report = self.load_report.clone();
+        let upstream = self.upstream.clone();
+        let upstream_unreadable = self.upstream_unreadable.clone();
+        let saved = self.saved.clone();
+        let date_format = date_format.to_string();
+        move |result| match result {
+            Ok(mut report) => {
+                if is_upstream {
+                    *upstream.lock().unwrap() = report.groups.clone();
+                    let mut unreadable: Vec<String> = report.broken.keys().cloned().collect();
+                    unreadable.sort();
+                    *upstream_unreadable.lock().unwrap() = unreadable;
+                }
+                *saved.lock().unwrap() = report.groups.clone();
+                report.normalise_dates(&date_format);
+                *group_data.lock().unwrap() = std::mem::take(&mut report.groups);
+                *load_report.lock().unwrap() = report;
+            }
+            Err(e) => *load_report.lock().unwrap() = LoadReport::failed(e),
+        }
+    }
+
+    /// Writes the groups to the document's file.
+    pub fn save(&mut self) -> Result<PathBuf, String> {
+        let groups = self.group_data.lock().unwrap().clone();
+        self.save_groups(&groups)
+    }
+
+    /// Writes `groups`, the document's locked data, to its file. A document
+    /// that didn't come from a file is backed by the file it was saved to
+    /// from then on.
+    pub fn save_groups(&mut self, groups: &HashMap<String, GroupData>) -> Result<PathBuf, String> {
+        let path = self.source.save_path();
+        let broken = self.load_report.lock().unwrap().broken.clone();
+        std::fs::write(&path, types::document_json(groups, &broken))
+            .map_err(|e| format!("Error writing {}: {}", path.display(), e))?;
+        *self.saved.lock().unwrap() = groups.clone();
+        self.source = Source::File(path.clone());
+        Ok(path)
+    }
+
+    /// Applies `edit` to the groups as a single undo step, for edits made
+    /// to a document other than the one being shown.
+    pub fn edit(&mut self, edit: impl FnOnce(&mut HashMap<String, GroupData>)) {
+        let shared = self.group_data.clone();
+        let mut groups = shared.lock().unwrap();
+        self.history.commit(&groups);
+        edit(&mut groups);
+        self.history.commit(&groups);
+    }
+}
+
+/// What to copy or move between documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Group(String),
+    Project { group: String, index: usize },
+}
+
+/// Copies `item` from `from` into `to`, removing it from `from` when
+/// `remove` is set. A group replaces one with the same name; a project goes
+/// into `to_group`, replacing a project with the same name.
+pub fn transfer(
+    from: &mut Document,
+    to: &mut Document,
+    item: &Item,
+    to_group: &str,
+    remove: bool,
+) -> Result<(), String> {
+    match item {
+        Item::Group(name) => {
+            let group = from
+                .group_data
+                .lock()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Group {} not found", name))?;
+            to.edit(|groups| {
+                groups.insert(name.clone(), group);
+            });
+            if remove {
+                from.edit(|groups| {
+                    groups.remove(name);
+                });
+            }
+        }
+        Item::Project { group, index } => {
+            let project = from
+                .group_data
+                .lock()
+                .unwrap()
+                .get(group)
+                .and_then(|g| g.projects.get(*index))
+                .cloned()
+                .ok_or_else(|| format!("Project {} in {} not found", index, group))?;
+            if !to.group_data.lock().unwrap().contains_key(to_group) {
+                return Err(format!("Group {} not found", to_group));
+            }
+            to.edit(|groups| {
+                let projects = &mut groups.get_mut(to_group).unwrap().projects;
+                match projects.iter_mut().find(|p| p.name == project.name) {
+                    Some(existing) => *existing = project,
+                    None => projects.push(project),
+                }
+            });
+            if remove {
+                from.edit(|groups| {
+                    if let Some(group) = groups.get_mut(group) {
+                        group.projects.remove(*index);
+                    }
+                });
+                if from.selected_group == *group {
+                    from.selected_project = None;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Mutable references to two different documents.
+pub fn pair_mut(documents: &mut [Document], a: usize, b: usize) -> (&mut Document, &mut Document) {
+    assert_ne!(a, b);
+    if a < b {
+        let (left, right) = documents.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = documents.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}